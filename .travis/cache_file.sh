@@ -7,10 +7,50 @@ then
     CACHEDIR=`dirname $0`/../.cached
 fi
 
+# Transient failures (timeouts, 5xx) are retried with exponential backoff,
+# client errors (4xx) abort immediately.
+MAX_ATTEMPTS=${CACHE_FILE_MAX_ATTEMPTS:-5}
+BASE_URL=${CACHE_FILE_BASE_URL:-https://s3.amazonaws.com/tract-ci-builds/tests}
+
 mkdir -p $CACHEDIR
 
 cd $CACHEDIR
+
+fetch() {
+    file=$1
+    attempt=1
+    delay=1
+    while true
+    do
+        if wget -S -q -T 60 -t 1 -O $file.part $BASE_URL/$file 2> $file.log
+        then
+            mv $file.part $file
+            rm -f $file.log
+            return 0
+        fi
+        status=`awk '/^ *HTTP\// { status = $2 } END { print status }' $file.log`
+        rm -f $file.part
+        case "$status" in
+            4??)
+                echo "Failed to download $file: HTTP $status" >&2
+                rm -f $file.log
+                return 1
+                ;;
+        esac
+        if [ $attempt -ge $MAX_ATTEMPTS ]
+        then
+            echo "Failed to download $file after $attempt attempts (last status: ${status:-none})" >&2
+            rm -f $file.log
+            return 1
+        fi
+        echo "Download of $file failed (status: ${status:-none}), retrying in ${delay}s" >&2
+        sleep $delay
+        attempt=$((attempt + 1))
+        delay=$((delay * 2))
+    done
+}
+
 for file in $@
 do
-     [ -e $file ] || wget -q https://s3.amazonaws.com/tract-ci-builds/tests/$file
+     [ -e $file ] || fetch $file
 done
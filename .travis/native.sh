@@ -75,6 +75,7 @@ cargo -q test -q --release -p tf-moz-deepspeech $ALL_FEATURES
 
 cargo -q build -q -p tract --release
 
+./.travis/test_cache_file.sh
 ./.travis/cache_file.sh \
     ARM-ML-KWS-CNN-M.pb \
     GRU128KeywordSpotter-v2-10epochs.onnx \
@@ -0,0 +1,82 @@
+#!/bin/sh
+
+# Check cache_file.sh retry and backoff against a local server whose
+# responses are scripted per file: `flaky` fails twice with a 503 then
+# succeeds, `missing` is a 404, `down` always fails with a 503.
+
+set -e
+
+SCRIPT=$(realpath `dirname $0`/cache_file.sh)
+WORKDIR=`mktemp -d`
+PORT=${TEST_CACHE_FILE_PORT:-8765}
+
+cat > $WORKDIR/server.py <<'PY'
+import http.server, sys
+
+responses = {
+    "/flaky": [503, 503, 200],
+    "/missing": [404],
+    "/down": [503],
+}
+hits = {}
+
+class Handler(http.server.BaseHTTPRequestHandler):
+    def do_GET(self):
+        hits[self.path] = hits.get(self.path, 0) + 1
+        script = responses.get(self.path, [404])
+        status = script[min(hits[self.path], len(script)) - 1]
+        with open("hits/" + self.path.strip("/"), "w") as f:
+            f.write(str(hits[self.path]))
+        self.send_response(status)
+        self.end_headers()
+        if status == 200:
+            self.wfile.write(b"payload")
+
+    def log_message(self, *args):
+        pass
+
+http.server.HTTPServer(("127.0.0.1", int(sys.argv[1])), Handler).serve_forever()
+PY
+
+mkdir -p $WORKDIR/hits $WORKDIR/cache
+( cd $WORKDIR && exec python3 server.py $PORT ) &
+SERVER=$!
+trap "kill $SERVER; rm -rf $WORKDIR" EXIT
+sleep 1
+
+export CACHEDIR=$WORKDIR/cache
+export CACHE_FILE_BASE_URL=http://127.0.0.1:$PORT
+export CACHE_FILE_MAX_ATTEMPTS=3
+
+fail() {
+    echo "test_cache_file: $1" >&2
+    exit 1
+}
+
+hits() {
+    cat $WORKDIR/hits/$1 2>/dev/null || echo 0
+}
+
+# transient failures are retried, with a 1s then 2s backoff
+start=`date +%s`
+$SCRIPT flaky || fail "flaky: expected success"
+[ "`cat $CACHEDIR/flaky`" = "payload" ] || fail "flaky: wrong content"
+[ `hits flaky` = 3 ] || fail "flaky: expected 3 requests, got `hits flaky`"
+[ $((`date +%s` - start)) -ge 3 ] || fail "flaky: retried without backoff"
+[ ! -e $CACHEDIR/flaky.part -a ! -e $CACHEDIR/flaky.log ] || fail "flaky: leftovers"
+
+# cached files are not fetched again
+$SCRIPT flaky || fail "flaky: expected cache hit"
+[ `hits flaky` = 3 ] || fail "flaky: fetched a cached file"
+
+# client errors abort immediately
+! $SCRIPT missing 2> /dev/null || fail "missing: expected failure"
+[ `hits missing` = 1 ] || fail "missing: expected 1 request, got `hits missing`"
+[ ! -e $CACHEDIR/missing ] || fail "missing: file created"
+
+# attempts are capped
+! $SCRIPT down 2> /dev/null || fail "down: expected failure"
+[ `hits down` = 3 ] || fail "down: expected 3 requests, got `hits down`"
+[ ! -e $CACHEDIR/down -a ! -e $CACHEDIR/down.part ] || fail "down: leftovers"
+
+echo "test_cache_file: ok"
@@ -23,6 +23,10 @@ pub fn handle(params: &Parameters, limits: &BenchLimits, probe: Option<&Probe>)
     let plan = SimplePlan::new(model)?;
     let mut state = SimpleState::new(plan)?;
 
+    for _ in 0..limits.warmup {
+        state.run(crate::tensor::make_inputs_for_model(model)?)?;
+    }
+
     let progress = probe.and_then(|m| m.get_i64("progress"));
     info!("Starting bench itself");
     let mut iters = 0;
@@ -48,3 +52,54 @@ pub fn handle(params: &Parameters, limits: &BenchLimits, probe: Option<&Probe>)
 
     Ok(())
 }
+
+/// Time `iters` runs one by one after `limits.warmup` untimed ones, reusing the
+/// same inputs, and report min/median/max.
+pub fn steady_state(params: &Parameters, limits: &BenchLimits, iters: usize) -> CliResult<()> {
+    if iters == 0 {
+        bail!("Steady state bench needs at least one iteration")
+    }
+    let model =
+        params.tract_model.downcast_ref::<TypedModel>().context("Can only bench TypedModel")?;
+    let plan = SimplePlan::new(model)?;
+    let mut state = SimpleState::new(plan)?;
+    let inputs = crate::tensor::make_inputs_for_model(model)?;
+
+    info!("Warming up for {} iterations", limits.warmup);
+    for _ in 0..limits.warmup {
+        state.run(inputs.clone())?;
+    }
+    info!("Starting bench itself");
+    let mut durations = Vec::with_capacity(iters);
+    for _ in 0..iters {
+        let inputs = inputs.clone();
+        let start = Instant::now();
+        state.run(inputs)?;
+        durations.push(start.elapsed());
+    }
+    durations.sort();
+    let min = durations[0];
+    let median = durations[iters / 2];
+    let max = durations[iters - 1];
+    let total: Duration = durations.iter().sum();
+    let per_sec = iters as f64 / total.as_secs_f64();
+
+    if params.machine_friendly {
+        println!("min: {}", min.as_secs_f64());
+        println!("median: {}", median.as_secs_f64());
+        println!("max: {}", max.as_secs_f64());
+        println!("iters_per_sec: {}", per_sec);
+    } else {
+        println!(
+            "Bench ran {} times after {} warmup runs, min {} median {} max {}, {:.1} i/s.",
+            iters,
+            limits.warmup,
+            terminal::dur_avg(min),
+            terminal::dur_avg(median),
+            terminal::dur_avg(max),
+            per_sec
+        );
+    }
+
+    Ok(())
+}
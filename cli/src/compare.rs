@@ -15,11 +15,16 @@ use crate::*;
 pub fn handle_tensorflow(
     cumulative: bool,
     resilient: bool,
+    skip_unsupported: bool,
     params: &mut Parameters,
     output_params: DisplayParams,
+    tolerances: &Tolerances,
 ) -> CliResult<()> {
     let tract = &params.tract_model;
-    let mut tf = params.tf_model.take().unwrap();
+    let mut tf = TensorflowExecutor {
+        tf: params.tf_model.take().unwrap(),
+        resilient: resilient || skip_unsupported,
+    };
     // First generate random values for the inputs.
     let input_facts = tract
         .input_outlets()
@@ -30,64 +35,50 @@ pub fn handle_tensorflow(
 
     // Execute the model on tensorflow first.
     info!("Running the model on tensorflow.");
-    trace!("Inject inputs in tensorflow graph.");
-    let pairs: Vec<_> = tract
+    let pairs: Vec<(String, Arc<Tensor>)> = tract
         .input_outlets()
         .iter()
-        .map(|s| &*tract.node_name(s.node))
-        .zip(generated.iter().cloned())
+        .map(|s| tract.node_name(s.node).to_string())
+        .zip(generated.iter().map(|t| t.clone().into_arc_tensor()))
         .collect();
 
-    trace!("Execute the model on tensorflow.");
     let eval_order = tract.eval_order()?;
 
-    let mut wanted_outputs: Vec<&str> = eval_order
+    let mut wanted_outputs: Vec<String> = eval_order
         .iter()
         .filter(|&n| !tract.input_outlets().contains(&OutletId::new(*n, 0)))
-        .map(|&n| tract.node_name(n))
+        .map(|&n| tract.node_name(n).to_string())
         .collect();
 
     for o in tract.output_outlets() {
-        let name = &*tract.node_name(o.node);
-        if !wanted_outputs.contains(&name) {
-            wanted_outputs.push(name);
+        let name = tract.node_name(o.node);
+        if !wanted_outputs.iter().any(|w| w == name) {
+            wanted_outputs.push(name.to_string());
         }
     }
 
-    let mut all_values: HashMap<String, CliResult<Arc<Tensor>>> = HashMap::new();
-    if resilient {
-        for name in wanted_outputs {
-            all_values.insert(
-                name.to_string(),
-                tf.run(pairs.clone(), &name)
-                    .map(|t| Arc::new(t[0].clone().into()))
-                    .map_err(|e| e.into()),
-            );
-        }
-    } else {
-        tf.run_get_many(pairs, wanted_outputs)?.into_iter().for_each(|(k, v)| {
-            all_values.insert(k.to_string(), Ok(v[0].clone().into()));
-        });
-    };
-
-    for (ix, input) in tract.input_outlets().iter().enumerate() {
-        let name = tract.node_name(input.node);
-        all_values.insert(name.to_string(), Ok(generated[ix].clone()));
+    let mut all_values = tf.run(&pairs, &wanted_outputs)?;
+    for (name, input) in pairs {
+        all_values.insert(name, Ok(input));
     }
     dispatch_model_no_pulse!(params.tract_model, |m| compare(
         cumulative,
+        skip_unsupported,
         m,
         &all_values,
         &params,
-        &output_params
+        &output_params,
+        tolerances
     ))
 }
 
 pub fn handle_npz(
     cumulative: bool,
+    skip_unsupported: bool,
     npz: &str,
     params: &Parameters,
     output_params: &DisplayParams,
+    tolerances: &Tolerances,
 ) -> CliResult<()> {
     let mut npz = ndarray_npy::NpzReader::new(std::fs::File::open(npz)?)?;
     let mut values = HashMap::new();
@@ -99,19 +90,23 @@ pub fn handle_npz(
     }
     dispatch_model_no_pulse!(params.tract_model, |m| compare(
         cumulative,
+        skip_unsupported,
         m,
         &values,
         &params,
-        output_params
+        output_params,
+        tolerances
     ))
 }
 
 #[cfg(feature = "onnx")]
 pub fn handle_pbdir(
     cumulative: bool,
+    skip_unsupported: bool,
     pbdir: &str,
     params: &Parameters,
     output_params: &DisplayParams,
+    tolerances: &Tolerances,
 ) -> CliResult<()> {
     let mut values: HashMap<String, CliResult<Arc<Tensor>>> = HashMap::new();
     for entry in fs::read_dir(pbdir)? {
@@ -123,56 +118,660 @@ pub fn handle_pbdir(
     }
     dispatch_model_no_pulse!(params.tract_model, |m| compare(
         cumulative,
+        skip_unsupported,
         m,
         &values,
         &params,
-        output_params
+        output_params,
+        tolerances
     ))
 }
 
 pub fn handle_reference_stage(
     cumulative: bool,
+    skip_unsupported: bool,
     params: &Parameters,
     output_params: &DisplayParams,
+    tolerances: &Tolerances,
 ) -> CliResult<()> {
     let reference_model =
         params.reference_model.as_ref().context("No reference model. need --with ?")?;
     let reference_model = reference_model
         .downcast_ref::<TypedModel>()
         .context("Only work with a typed reference model")?;
-    let mut values: HashMap<String, CliResult<Arc<Tensor>>> = HashMap::new();
+    let values = TractExecutor(reference_model)
+        .all_values(crate::tensor::retrieve_or_make_inputs(reference_model, params)?)?;
+    dispatch_model_no_pulse!(params.tract_model, |m| compare(
+        cumulative,
+        skip_unsupported,
+        m,
+        &values,
+        params,
+        output_params,
+        tolerances
+    ))
+}
 
-    let plan = SimplePlan::new(reference_model)?;
-    let mut state = SimpleState::new(plan)?;
-    state.run_plan_with_eval(
-        crate::tensor::retrieve_or_make_inputs(reference_model, params)?,
-        |session, state, node, input| -> TractResult<_> {
+/// A way to compute the values of a model, to be compared to another one by
+/// `compare_executors`: tract against TensorFlow, or two tract models.
+pub trait Executor {
+    /// Compute `outputs` (node names or outlet labels), feeding `inputs` by
+    /// node name. An output that can not be computed gets an error entry.
+    fn run(
+        &mut self,
+        inputs: &[(String, Arc<Tensor>)],
+        outputs: &[String],
+    ) -> CliResult<HashMap<String, CliResult<Arc<Tensor>>>>;
+}
+
+/// Runs a typed model with tract.
+pub struct TractExecutor<'m>(pub &'m TypedModel);
+
+impl<'m> TractExecutor<'m> {
+    /// Run the model, returning the value of every node by node name and
+    /// outlet label.
+    pub fn all_values(
+        &self,
+        inputs: TVec<Tensor>,
+    ) -> CliResult<HashMap<String, CliResult<Arc<Tensor>>>> {
+        let model = self.0;
+        let mut values = HashMap::new();
+        let plan = SimplePlan::new(model)?;
+        let mut state = SimpleState::new(plan)?;
+        state.run_plan_with_eval(inputs, |session, state, node, input| -> TractResult<_> {
             let result: TVec<Arc<Tensor>> = tract_core::plan::eval(session, state, node, input)?;
-            values.insert(node.name.clone(), Ok(result[0].clone()));
+            if let Some(value) = result.get(0) {
+                values.insert(node.name.clone(), Ok(value.clone()));
+            }
             for (output_slot, v) in result.iter().enumerate() {
-                if let Some(tag) = reference_model.outlet_label((node.id, output_slot).into()) {
+                if let Some(tag) = model.outlet_label((node.id, output_slot).into()) {
                     values.insert(tag.to_string(), Ok(v.clone()));
                 }
             }
             Ok(result)
-        },
-    )?;
-    dispatch_model_no_pulse!(params.tract_model, |m| compare(
-        cumulative,
-        m,
-        &values,
-        params,
-        output_params
-    ))
+        })?;
+        Ok(values)
+    }
 }
 
-pub fn compare<F, O>(
+impl<'m> Executor for TractExecutor<'m> {
+    fn run(
+        &mut self,
+        inputs: &[(String, Arc<Tensor>)],
+        outputs: &[String],
+    ) -> CliResult<HashMap<String, CliResult<Arc<Tensor>>>> {
+        let inputs = self
+            .0
+            .input_outlets()?
+            .iter()
+            .map(|o| {
+                let name = &self.0.node(o.node).name;
+                inputs
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, t)| t.clone().into_tensor())
+                    .with_context(|| format!("No value for input {}", name))
+            })
+            .collect::<CliResult<TVec<Tensor>>>()?;
+        let mut values = self.all_values(inputs)?;
+        Ok(outputs
+            .iter()
+            .map(|name| {
+                let value = values
+                    .remove(name)
+                    .unwrap_or_else(|| Err(format_err!("{} was not computed by tract", name)));
+                (name.clone(), value)
+            })
+            .collect())
+    }
+}
+
+/// Runs a graph with TensorFlow.
+#[cfg(feature = "conform")]
+pub struct TensorflowExecutor {
+    pub tf: tract_tensorflow::conform::tf::Tensorflow,
+    /// Compute outputs one by one, so that a failing one does not fail the
+    /// others.
+    pub resilient: bool,
+}
+
+#[cfg(feature = "conform")]
+impl Executor for TensorflowExecutor {
+    fn run(
+        &mut self,
+        inputs: &[(String, Arc<Tensor>)],
+        outputs: &[String],
+    ) -> CliResult<HashMap<String, CliResult<Arc<Tensor>>>> {
+        trace!("Execute the model on tensorflow.");
+        let pairs: Vec<(&str, Tensor)> =
+            inputs.iter().map(|(name, t)| (&**name, t.clone().into_tensor())).collect();
+        let mut values = HashMap::new();
+        if self.resilient {
+            for name in outputs {
+                values.insert(
+                    name.clone(),
+                    self.tf
+                        .run(pairs.clone(), name)
+                        .map(|t| t[0].clone().into_arc_tensor())
+                        .map_err(|e| e.into()),
+                );
+            }
+        } else {
+            let names = outputs.iter().map(|name| &**name).collect();
+            for (k, v) in self.tf.run_get_many(pairs, names)? {
+                values.insert(k.to_string(), Ok(v[0].clone().into_arc_tensor()));
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Compare the values of `outputs` (node names or outlet labels of `model`)
+/// computed by two executors from the same `inputs`, given in the order of
+/// the model inputs. Without `outputs`, the model outputs are compared.
+///
+/// With `skip_unsupported`, outputs the reference fails to compute are
+/// reported as skipped instead of failing the run.
+pub fn compare_executors(
+    found: &mut dyn Executor,
+    reference: &mut dyn Executor,
+    model: &dyn Model,
+    inputs: &[Arc<Tensor>],
+    outputs: &[String],
+    tolerances: &Tolerances,
+    skip_unsupported: bool,
+) -> CliResult<CompareReport> {
+    if inputs.len() != model.input_outlets().len() {
+        bail!("Expected {} input(s), got {}", model.input_outlets().len(), inputs.len())
+    }
+    let inputs = model
+        .input_outlets()
+        .iter()
+        .map(|o| model.node_name(o.node).to_string())
+        .zip(inputs.iter().cloned())
+        .collect::<Vec<_>>();
+    let outputs = if outputs.is_empty() {
+        model
+            .output_outlets()
+            .iter()
+            .map(|&o| model.outlet_label(o).unwrap_or(model.node_name(o.node)).to_string())
+            .collect()
+    } else {
+        outputs.to_vec()
+    };
+    let reference_values = reference.run(&inputs, &outputs)?;
+    let found_values = found.run(&inputs, &outputs)?;
+    let mut report = CompareReport::default();
+    for name in &outputs {
+        let (id, slot) = outlet_for_name(model, name)?;
+        let options = tolerances.for_output(name, model.node_name(id));
+        let mut node = NodeReport {
+            id,
+            name: name.clone(),
+            status: NodeStatus::Passed,
+            outputs: vec![],
+            inputs: vec![],
+        };
+        match found_values.get(name) {
+            Some(Ok(found)) => {
+                let output = match reference_values.get(name) {
+                    Some(Ok(r)) => OutputReport::compare(slot, name, found, r, options),
+                    Some(Err(e)) if skip_unsupported => {
+                        let mut output = OutputReport::new(slot, name, OutputStatus::Skipped);
+                        output.message = Some(format!("unsupported in reference: {}", e));
+                        output
+                    }
+                    Some(Err(e)) => {
+                        let mut output =
+                            OutputReport::new(slot, name, OutputStatus::ReferenceError);
+                        output.message = Some(e.to_string());
+                        output
+                    }
+                    None => OutputReport::new(slot, name, OutputStatus::NoReference),
+                };
+                node.status = match output.status {
+                    OutputStatus::Ok => NodeStatus::Passed,
+                    OutputStatus::NoReference => NodeStatus::NoReference,
+                    OutputStatus::Skipped => {
+                        NodeStatus::Skipped(output.message.clone().unwrap_or_default())
+                    }
+                    OutputStatus::Mismatch | OutputStatus::ReferenceError => NodeStatus::Failed,
+                };
+                node.outputs.push(output);
+            }
+            Some(Err(e)) => node.status = NodeStatus::Error(e.to_string()),
+            None => node.status = NodeStatus::Error(format!("{} was not computed", name)),
+        }
+        report.push(node);
+    }
+    report.passed = report.nodes_failed == 0;
+    Ok(report)
+}
+
+/// Compare a typed model running in tract against TensorFlow running the
+/// graph it was imported from.
+#[cfg(feature = "conform")]
+pub fn compare_tensorflow(
+    tf: tract_tensorflow::conform::tf::Tensorflow,
+    model: &TypedModel,
+    inputs: &[Arc<Tensor>],
+    outputs: &[String],
+    tolerances: &Tolerances,
+    skip_unsupported: bool,
+) -> CliResult<CompareReport> {
+    compare_executors(
+        &mut TractExecutor(model),
+        &mut TensorflowExecutor { tf, resilient: true },
+        model,
+        inputs,
+        outputs,
+        tolerances,
+        skip_unsupported,
+    )
+}
+
+/// Find an outlet by node name (first output) or outlet label.
+fn outlet_for_name(model: &dyn Model, name: &str) -> CliResult<(usize, usize)> {
+    if let Ok(id) = model.node_id_by_name(name) {
+        return Ok((id, 0));
+    }
+    (0..model.nodes_len())
+        .flat_map(|id| (0..model.node_output_count(id)).map(move |slot| (id, slot)))
+        .find(|&(id, slot)| model.outlet_label(OutletId::new(id, slot)) == Some(name))
+        .with_context(|| format!("No node or outlet label {} in model", name))
+}
+
+/// Tolerances for approximate comparison of a value against its reference.
+///
+/// Values `found` and `reference` match if
+/// `|found - reference| <= atol + rtol * |reference|`.
+///
+/// With a `dynamic_axis`, the two tensors may differ in length on this axis
+/// (e.g. a variable number of detections): only the common prefix is
+/// compared, and the length difference is reported without failing.
+///
+/// With `quantization` (scale and zero point), integer outputs are
+/// dequantized to f32 before comparison, so the tolerances apply to real
+/// values instead of raw quantized ones.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CompareOptions {
+    pub atol: f32,
+    pub rtol: f32,
+    pub dynamic_axis: Option<usize>,
+    pub quantization: Option<(f32, i32)>,
+}
+
+impl Default for CompareOptions {
+    fn default() -> CompareOptions {
+        CompareOptions { atol: 5e-4, rtol: 1e-4, dynamic_axis: None, quantization: None }
+    }
+}
+
+/// Tolerances for a comparison run: a default, and overrides by output name
+/// (node name or outlet label).
+#[derive(Clone, Debug, Default)]
+pub struct Tolerances {
+    pub default: CompareOptions,
+    pub per_output: HashMap<String, CompareOptions>,
+}
+
+impl Tolerances {
+    /// Options for an outlet, overridden by its label first, then by its
+    /// node name.
+    pub fn for_output(&self, label: &str, node_name: &str) -> CompareOptions {
+        self.per_output
+            .get(label)
+            .or_else(|| self.per_output.get(node_name))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    pub fn from_clap(matches: &clap::ArgMatches) -> CliResult<Tolerances> {
+        let mut default = CompareOptions::default();
+        if let Some(atol) = matches.value_of("atol") {
+            default.atol = atol.parse()?;
+        }
+        if let Some(rtol) = matches.value_of("rtol") {
+            default.rtol = rtol.parse()?;
+        }
+        let mut dynamic_axes = vec![];
+        for spec in matches.values_of("dynamic-axis").into_iter().flatten() {
+            if let Some((axis, name)) = spec.rsplitn(2, '=').collect_tuple() {
+                dynamic_axes.push((name, axis, spec));
+            } else {
+                default.dynamic_axis = Some(spec.parse()?);
+            }
+        }
+        let mut quantizations = vec![];
+        for spec in matches.values_of("quantization").into_iter().flatten() {
+            let (name, params) = match spec.rsplitn(2, '=').collect_tuple() {
+                Some((params, name)) => (Some(name), params),
+                None => (None, spec),
+            };
+            let (scale, zero_point) = params.split(',').collect_tuple().with_context(|| {
+                format!("Expected [name=]scale,zero_point for quantization, got {:?}", spec)
+            })?;
+            let params = (scale.parse()?, zero_point.parse()?);
+            if let Some(name) = name {
+                quantizations.push((name, params));
+            } else {
+                default.quantization = Some(params);
+            }
+        }
+        let mut per_output = HashMap::new();
+        for spec in matches.values_of("tolerance").into_iter().flatten() {
+            let mut split = spec.splitn(2, '=');
+            let name = split.next().unwrap();
+            let mut tols = split
+                .next()
+                .with_context(|| format!("Expected name=atol[,rtol], got {:?}", spec))?
+                .split(',');
+            let mut options = default;
+            options.atol = tols.next().unwrap().parse()?;
+            if let Some(rtol) = tols.next() {
+                options.rtol = rtol.parse()?;
+            }
+            per_output.insert(name.to_string(), options);
+        }
+        for (name, axis, spec) in dynamic_axes {
+            let axis = axis.parse().with_context(|| {
+                format!("Expected [name=]axis for dynamic axis, got {:?}", spec)
+            })?;
+            per_output.entry(name.to_string()).or_insert(default).dynamic_axis = Some(axis);
+        }
+        for (name, params) in quantizations {
+            per_output.entry(name.to_string()).or_insert(default).quantization = Some(params);
+        }
+        Ok(Tolerances { default, per_output })
+    }
+}
+
+/// Outcome of a node-by-node comparison, serializable for consumption by
+/// tooling (`compare --json`) and readable back from it. Tensors and input
+/// dumps are not serialized.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CompareReport {
+    pub passed: bool,
+    pub nodes_passed: usize,
+    pub nodes_failed: usize,
+    pub nodes_skipped: usize,
+    pub nodes: Vec<NodeReport>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeReport {
+    pub id: usize,
+    pub name: String,
+    pub status: NodeStatus,
+    pub outputs: Vec<OutputReport>,
+    #[serde(skip)]
+    pub inputs: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Input,
+    Random,
+    Unimplemented,
+    Error(String),
+    Passed,
+    Failed,
+    NoReference,
+    Skipped(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OutputReport {
+    pub slot: usize,
+    pub label: String,
+    pub status: OutputStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shape: Option<Vec<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference_shape: Option<Vec<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_abs_diff: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rel_diff: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worst: Option<Vec<usize>>,
+    /// Found and reference lengths on the dynamic axis, when they differ.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamic_lengths: Option<(usize, usize)>,
+    #[serde(skip)]
+    pub found: Option<Arc<Tensor>>,
+    #[serde(skip)]
+    pub reference: Option<Arc<Tensor>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStatus {
+    Ok,
+    Mismatch,
+    ReferenceError,
+    NoReference,
+    Skipped,
+}
+
+impl CompareReport {
+    fn push(&mut self, node: NodeReport) {
+        if node.status == NodeStatus::Passed {
+            self.nodes_passed += 1;
+        } else if node.is_failure() {
+            self.nodes_failed += 1;
+        } else if let NodeStatus::Skipped(_) = node.status {
+            self.nodes_skipped += 1;
+        }
+        self.nodes.push(node);
+    }
+}
+
+impl NodeReport {
+    pub fn is_failure(&self) -> bool {
+        match self.status {
+            NodeStatus::Unimplemented | NodeStatus::Error(_) | NodeStatus::Failed => true,
+            _ => false,
+        }
+    }
+}
+
+impl OutputReport {
+    fn new(slot: usize, label: &str, status: OutputStatus) -> OutputReport {
+        OutputReport {
+            slot,
+            label: label.to_string(),
+            status,
+            message: None,
+            shape: None,
+            reference_shape: None,
+            max_abs_diff: None,
+            max_rel_diff: None,
+            worst: None,
+            dynamic_lengths: None,
+            found: None,
+            reference: None,
+        }
+    }
+
+    fn compare(
+        slot: usize,
+        label: &str,
+        found: &Arc<Tensor>,
+        reference: &Arc<Tensor>,
+        options: CompareOptions,
+    ) -> OutputReport {
+        let mut report = OutputReport::new(slot, label, OutputStatus::Ok);
+        report.shape = Some(found.shape().to_vec());
+        report.reference_shape = Some(reference.shape().to_vec());
+        let (found, reference) = match options.dynamic_axis {
+            Some(axis) if found.shape() != reference.shape() => {
+                match common_prefix(found, reference, axis) {
+                    Ok((f, r)) => {
+                        report.dynamic_lengths =
+                            Some((found.shape()[axis], reference.shape()[axis]));
+                        (f.into_arc_tensor(), r.into_arc_tensor())
+                    }
+                    Err(e) => {
+                        report.status = OutputStatus::Mismatch;
+                        report.message = Some(e.to_string());
+                        report.found = Some(found.clone());
+                        report.reference = Some(reference.clone());
+                        return report;
+                    }
+                }
+            }
+            _ => (found.clone(), reference.clone()),
+        };
+        let (found, reference) = match options.quantization {
+            Some((scale, zero_point))
+                if found.datum_type().is_integer() && reference.datum_type().is_integer() =>
+            {
+                let dequantize = |t: &Arc<Tensor>| {
+                    QuantizedTensor::new(t.clone().into_tensor(), scale, zero_point)
+                        .and_then(|q| q.dequantize())
+                        .map(|t| t.into_arc_tensor())
+                };
+                match (dequantize(&found), dequantize(&reference)) {
+                    (Ok(f), Ok(r)) => (f, r),
+                    (Err(e), _) | (_, Err(e)) => {
+                        report.status = OutputStatus::Mismatch;
+                        report.message = Some(e.to_string());
+                        report.found = Some(found);
+                        report.reference = Some(reference);
+                        return report;
+                    }
+                }
+            }
+            _ => (found, reference),
+        };
+        let (found, reference) = (&found, &reference);
+        if let Some((abs, rel, worst)) = diff_stats(found, reference) {
+            report.max_abs_diff = Some(abs);
+            report.max_rel_diff = Some(rel);
+            report.worst = Some(worst);
+        }
+        if let Err(e) = found.close_enough_with(reference, options.atol, options.rtol) {
+            report.status = OutputStatus::Mismatch;
+            report.message = Some(e.to_string());
+            report.found = Some(found.clone());
+            report.reference = Some(reference.clone());
+        }
+        report
+    }
+}
+
+/// Truncate two tensors to their common length along `axis`. All other axes
+/// must match.
+fn common_prefix(found: &Tensor, reference: &Tensor, axis: usize) -> CliResult<(Tensor, Tensor)> {
+    let (fs, rs) = (found.shape(), reference.shape());
+    if fs.len() != rs.len()
+        || axis >= fs.len()
+        || (0..fs.len()).any(|ix| ix != axis && fs[ix] != rs[ix])
+    {
+        bail!("Shape mismatch {:?} != {:?} (dynamic axis: {})", fs, rs, axis)
+    }
+    let len = fs[axis].min(rs[axis]);
+    Ok((found.slice(axis, 0, len)?, reference.slice(axis, 0, len)?))
+}
+
+/// Max absolute and relative differences between two same-shaped tensors,
+/// and the coordinate of the worst absolute one. A NaN on one side only
+/// counts as an infinite difference.
+pub fn diff_stats(found: &Tensor, reference: &Tensor) -> Option<(f32, f32, Vec<usize>)> {
+    use tract_core::ndarray::Dimension;
+    if found.shape() != reference.shape() {
+        return None;
+    }
+    let found = found.cast_to::<f32>().ok()?;
+    let reference = reference.cast_to::<f32>().ok()?;
+    let found = found.to_array_view::<f32>().ok()?;
+    let reference = reference.to_array_view::<f32>().ok()?;
+    let mut max_abs = 0f32;
+    let mut max_rel = 0f32;
+    let mut worst = vec![0; found.ndim()];
+    for ((coords, &f), &r) in found.indexed_iter().zip(reference.iter()) {
+        let abs = if f.is_nan() && r.is_nan() {
+            0.0
+        } else if f.is_nan() || r.is_nan() {
+            std::f32::INFINITY
+        } else {
+            (f - r).abs()
+        };
+        let rel = if abs == 0.0 { 0.0 } else { abs / r.abs().max(std::f32::MIN_POSITIVE) };
+        if abs > max_abs {
+            max_abs = abs;
+            worst = coords.slice().to_vec();
+        }
+        max_rel = max_rel.max(rel);
+    }
+    Some((max_abs, max_rel, worst))
+}
+
+/// Write the `top_k` biggest absolute differences between two same-shaped
+/// tensors as csv rows (`output,coord,found,reference,abs_diff,rel_diff`),
+/// sorted by decreasing absolute difference.
+///
+/// Values are widened to f64. A NaN on one side only counts as an infinite
+/// difference.
+pub fn write_diff_csv(
+    w: &mut impl std::io::Write,
+    label: &str,
+    found: &Tensor,
+    reference: &Tensor,
+    top_k: usize,
+) -> CliResult<()> {
+    if found.shape() != reference.shape() {
+        bail!("Shape mismatch {:?} != {:?}", found.shape(), reference.shape())
+    }
+    let mut diffs = found
+        .iter_indexed()?
+        .zip(reference.iter_indexed()?)
+        .map(|((coords, f), (_, r))| {
+            let abs = if f == r || (f.is_nan() && r.is_nan()) {
+                0.0
+            } else if f.is_nan() || r.is_nan() {
+                std::f64::INFINITY
+            } else {
+                (f - r).abs()
+            };
+            let rel = if abs == 0.0 { 0.0 } else { abs / r.abs().max(std::f64::MIN_POSITIVE) };
+            (coords, f, r, abs, rel)
+        })
+        .collect::<Vec<_>>();
+    diffs.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap());
+    for (coords, f, r, abs, rel) in diffs.into_iter().take(top_k) {
+        writeln!(
+            w,
+            "\"{}\",\"[{}]\",{},{},{},{}",
+            label.replace('"', "\"\""),
+            coords.iter().join(", "),
+            f,
+            r,
+            abs,
+            rel
+        )?;
+    }
+    Ok(())
+}
+
+/// Run tract node by node against reference values, indexed by node name or
+/// outlet label.
+///
+/// With `skip_unsupported`, unimplemented tract nodes and outputs the reference
+/// fails to compute are reported as skipped instead of failing the run. The
+/// following nodes run on the reference values of a skipped node, or are
+/// skipped too if there are none.
+pub fn compare_report<F, O>(
     cumulative: bool,
+    skip_unsupported: bool,
     tract: &Graph<F, O>,
     all_values: &HashMap<String, CliResult<Arc<Tensor>>>,
-    params: &Parameters,
-    output_params: &DisplayParams,
-) -> CliResult<()>
+    tolerances: &Tolerances,
+) -> CliResult<CompareReport>
 where
     F: Fact + Clone + for<'a> From<&'a Tensor> + Hash,
     O: AsRef<dyn Op> + AsMut<dyn Op> + Display + Debug + Clone + Hash,
@@ -190,31 +789,56 @@ where
         state.set_input(ix, value.clone().into_tensor())?;
     }
 
-    let mut annotations = crate::annotations::Annotations::from_model(tract as &dyn Model)?
-        .with_graph_def(tract, &params.graph)?;
-
-    let mut failing = vec![];
-    let mut ok = 0;
+    let mut report = CompareReport::default();
+    // skipped nodes with no value for the following ones to run on
+    let mut skipped_without_values = std::collections::HashSet::new();
 
     for n in eval_order {
         let node = &tract.nodes()[n];
-        let mut ok_node = true;
-
-        let mut tags = annotations.node_mut(n.into());
+        let mut node_report = NodeReport {
+            id: n,
+            name: node.name.clone(),
+            status: NodeStatus::Passed,
+            outputs: vec![],
+            inputs: vec![],
+        };
 
         if tract.input_outlets()?.iter().any(|o| o.node == n) {
-            tags.style = Some(Blue.into());
+            node_report.status = NodeStatus::Input;
         } else if node.op().validation() == Validation::Random {
-            tags.style = Some(Blue.into());
-            tags.labels.push(Blue.paint("Random").to_string());
+            node_report.status = NodeStatus::Random;
         } else if node.op_is::<tract_core::ops::unimpl::UnimplementedOp>() {
-            tags.style = Some(Red.into());
-            tags.labels.push(Red.paint("Unimplemented").to_string());
-            failing.push(n);
+            if skip_unsupported {
+                let op = node.op().name();
+                let op = op.trim_start_matches("Unimplemented(").trim_end_matches(')');
+                node_report.status = NodeStatus::Skipped(format!("unsupported in tract: {}", op));
+                // let the following nodes run on the reference values
+                let values = (0..node.outputs.len())
+                    .map(|ix| {
+                        let label = tract.outlet_label((n, ix).into()).unwrap_or(&node.name);
+                        all_values.get(label).and_then(|v| v.as_ref().ok()).cloned()
+                    })
+                    .collect::<Option<TVec<_>>>();
+                if values.is_some() {
+                    state.values[n] = values;
+                } else {
+                    skipped_without_values.insert(n);
+                }
+            } else {
+                node_report.status = NodeStatus::Unimplemented;
+            }
+        } else if let Some(skipped) =
+            node.inputs.iter().find(|i| skipped_without_values.contains(&i.node))
+        {
+            node_report.status = NodeStatus::Skipped(format!(
+                "depends on skipped node {}",
+                tract.node_name(skipped.node)
+            ));
+            skipped_without_values.insert(n);
         } else {
             debug!("Computing {} in tract", node);
             let error = state.compute_recursively(n).err();
-            let inputs = tract.nodes()[n]
+            node_report.inputs = tract.nodes()[n]
                 .inputs
                 .iter()
                 .enumerate()
@@ -228,68 +852,509 @@ where
                 })
                 .collect::<Vec<_>>();
             if let Some(e) = error {
-                failing.push(n);
-                tags.style = Some(Red.into());
-                tags.labels.push(format!("{}: {}", Red.bold().paint("ERROR"), e));
+                node_report.status = NodeStatus::Error(e.to_string());
             } else {
                 for ix in 0..node.outputs.len() {
                     let label = tract.outlet_label((n, ix).into()).unwrap_or(&node.name);
-                    if let Some(ref_value) = all_values.get(label) {
+                    let output = if let Some(ref_value) = all_values.get(label) {
                         match ref_value {
                             Ok(t) => {
                                 let found = &state.values[n].as_ref().unwrap()[ix];
-                                if let Err(e) = found.close_enough(t, true) {
-                                    failing.push(n);
-                                    ok_node = false;
-                                    tags.style = Some(Red.bold());
-                                    let mut msg = vec![Red
-                                        .bold()
-                                        .paint(format!("Wrong value for output {}, {}", ix, e))
-                                        .to_string()];
-                                    msg.push(format!("got     : {:?}", found));
-                                    msg.push(format!("ref     : {:?}", t));
-                                    tags.sections.push(msg);
-                                }
+                                let output = OutputReport::compare(
+                                    ix,
+                                    label,
+                                    found,
+                                    t,
+                                    tolerances.for_output(label, &node.name),
+                                );
                                 if !cumulative {
                                     // Use the output from reference to keep tract from drifting.
                                     state.values[node.id].as_mut().unwrap()[ix] =
                                         t.to_owned().into_arc_tensor();
                                 }
+                                output
+                            }
+                            Err(e) if skip_unsupported => {
+                                let mut output =
+                                    OutputReport::new(ix, label, OutputStatus::Skipped);
+                                output.message = Some(format!("unsupported in reference: {}", e));
+                                output
                             }
                             Err(e) => {
-                                failing.push(n);
-                                ok_node = false;
-                                tags.style = Some(Red.bold());
-                                tags.labels.push(format!("{}: {}", Red.bold().paint("ERROR"), e));
+                                let mut output =
+                                    OutputReport::new(ix, label, OutputStatus::ReferenceError);
+                                output.message = Some(e.to_string());
+                                output
                             }
                         }
                     } else {
-                        ok_node = false;
-                        tags.style = Some(White.bold().into());
-                        tags.labels.push(White.paint("No matching wire in reference").to_string());
-                    }
+                        OutputReport::new(ix, label, OutputStatus::NoReference)
+                    };
+                    node_report.outputs.push(output);
                 }
-                tags.sections.push(inputs);
-                if ok_node {
-                    tags.style = Some(Green.bold());
-                    ok += 1;
+                let statuses = node_report.outputs.iter().map(|o| o.status).collect::<Vec<_>>();
+                if statuses
+                    .iter()
+                    .any(|s| *s == OutputStatus::Mismatch || *s == OutputStatus::ReferenceError)
+                {
+                    node_report.status = NodeStatus::Failed;
+                } else if let Some(skipped) =
+                    node_report.outputs.iter().find(|o| o.status == OutputStatus::Skipped)
+                {
+                    node_report.status =
+                        NodeStatus::Skipped(skipped.message.clone().unwrap_or_default());
+                } else if statuses.iter().any(|s| *s == OutputStatus::NoReference) {
+                    node_report.status = NodeStatus::NoReference;
                 }
             }
         }
+        report.push(node_report);
     }
+    report.passed = report.nodes_failed == 0;
+    Ok(report)
+}
 
-    if log_enabled!(Info) {
-        terminal::render(tract, &annotations, &output_params)?;
-    } else {
-        for f in &failing {
-            terminal::render_node(tract, *f, &annotations, &output_params)?;
+pub fn compare<F, O>(
+    cumulative: bool,
+    skip_unsupported: bool,
+    tract: &Graph<F, O>,
+    all_values: &HashMap<String, CliResult<Arc<Tensor>>>,
+    params: &Parameters,
+    output_params: &DisplayParams,
+    tolerances: &Tolerances,
+) -> CliResult<()>
+where
+    F: Fact + Clone + for<'a> From<&'a Tensor> + Hash,
+    O: AsRef<dyn Op> + AsMut<dyn Op> + Display + Debug + Clone + Hash,
+    Graph<F, O>: Model,
+{
+    let report = compare_report(cumulative, skip_unsupported, tract, all_values, tolerances)?;
+
+    if let Some(path) = &output_params.diff_csv {
+        use std::io::Write;
+        let mut csv = std::io::BufWriter::new(fs::File::create(path)?);
+        writeln!(csv, "output,coord,found,reference,abs_diff,rel_diff")?;
+        for output in report.nodes.iter().flat_map(|n| n.outputs.iter()) {
+            if let (Some(found), Some(reference)) = (&output.found, &output.reference) {
+                if found.shape() != reference.shape() {
+                    warn!(
+                        "Not writing {} to diff csv: shape {:?} differs from reference {:?}",
+                        output.label,
+                        found.shape(),
+                        reference.shape()
+                    );
+                    continue;
+                }
+                write_diff_csv(
+                    &mut csv,
+                    &output.label,
+                    found,
+                    reference,
+                    output_params.diff_csv_top_k,
+                )?;
+            }
         }
     }
 
-    if failing.len() > 0 {
-        bail!("{} error(s).", failing.len())
+    if output_params.json {
+        serde_json::to_writer(std::io::stdout(), &report)?;
     } else {
-        println!("{}", Green.paint(format!("{} node(s) passed the comparison.", ok)));
+        let annotations = annotate(tract, &report, params, output_params.dump_limit)?;
+        if log_enabled!(Info) {
+            terminal::render(tract, &annotations, &output_params)?;
+        } else {
+            for node in report.nodes.iter().filter(|n| n.is_failure()) {
+                terminal::render_node(tract, node.id, &annotations, &output_params)?;
+            }
+            for output in report.nodes.iter().flat_map(|n| n.outputs.iter()) {
+                if let Some((found, reference)) = output.dynamic_lengths {
+                    println!(
+                        "{}",
+                        Yellow.paint(format!(
+                            "{}: length {} on dynamic axis, {} in reference",
+                            output.label, found, reference
+                        ))
+                    );
+                }
+            }
+            for node in &report.nodes {
+                if let NodeStatus::Skipped(reason) = &node.status {
+                    println!("{}", Yellow.paint(format!("{}: skipped ({})", node.name, reason)));
+                }
+            }
+        }
+    }
+
+    if !report.passed {
+        bail!("{} error(s).", report.nodes_failed)
+    } else if !output_params.json {
+        println!(
+            "{}",
+            Green.paint(format!("{} node(s) passed the comparison.", report.nodes_passed))
+        );
+        if report.nodes_skipped > 0 {
+            println!("{}", Yellow.paint(format!("{} node(s) skipped.", report.nodes_skipped)));
+        }
     };
     Ok(())
 }
+
+fn annotate<F, O>(
+    tract: &Graph<F, O>,
+    report: &CompareReport,
+    params: &Parameters,
+    dump_limit: usize,
+) -> CliResult<crate::annotations::Annotations>
+where
+    F: Fact + Clone + for<'a> From<&'a Tensor> + Hash,
+    O: AsRef<dyn Op> + AsMut<dyn Op> + Display + Debug + Clone + Hash,
+    Graph<F, O>: Model,
+{
+    let mut annotations = crate::annotations::Annotations::from_model(tract as &dyn Model)?
+        .with_graph_def(tract, &params.graph)?;
+    for node in &report.nodes {
+        let tags = annotations.node_mut(node.id.into());
+        match &node.status {
+            NodeStatus::Input => tags.style = Some(Blue.into()),
+            NodeStatus::Random => {
+                tags.style = Some(Blue.into());
+                tags.labels.push(Blue.paint("Random").to_string());
+            }
+            NodeStatus::Unimplemented => {
+                tags.style = Some(Red.into());
+                tags.labels.push(Red.paint("Unimplemented").to_string());
+            }
+            NodeStatus::Error(e) => {
+                tags.style = Some(Red.into());
+                tags.labels.push(format!("{}: {}", Red.bold().paint("ERROR"), e));
+            }
+            NodeStatus::Passed => tags.style = Some(Green.bold()),
+            NodeStatus::Failed => tags.style = Some(Red.bold()),
+            NodeStatus::NoReference => tags.style = Some(White.bold().into()),
+            NodeStatus::Skipped(reason) => {
+                tags.style = Some(Yellow.into());
+                tags.labels.push(Yellow.paint(format!("Skipped ({})", reason)).to_string());
+            }
+        }
+        for output in &node.outputs {
+            if let Some((found, reference)) = output.dynamic_lengths {
+                tags.labels.push(
+                    Yellow
+                        .paint(format!(
+                            "Output {} length differs on dynamic axis: {} vs {} in reference",
+                            output.slot, found, reference
+                        ))
+                        .to_string(),
+                );
+            }
+            match output.status {
+                OutputStatus::Ok => (),
+                OutputStatus::Mismatch => {
+                    let mut msg = vec![Red
+                        .bold()
+                        .paint(format!(
+                            "Wrong value for output {}, {}",
+                            output.slot,
+                            output.message.as_deref().unwrap_or("")
+                        ))
+                        .to_string()];
+                    for (title, t) in &[("got", &output.found), ("ref", &output.reference)] {
+                        if let Some(t) = t {
+                            msg.push(format!("{:8}: {}", title, t.dump_limited(dump_limit)?));
+                        }
+                    }
+                    tags.sections.push(msg);
+                }
+                OutputStatus::ReferenceError => tags.labels.push(format!(
+                    "{}: {}",
+                    Red.bold().paint("ERROR"),
+                    output.message.as_deref().unwrap_or("")
+                )),
+                OutputStatus::NoReference => {
+                    tags.labels.push(White.paint("No matching wire in reference").to_string())
+                }
+                OutputStatus::Skipped => (),
+            }
+        }
+        if !node.inputs.is_empty() {
+            tags.sections.push(node.inputs.clone());
+        }
+    }
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn report(passed: bool) -> CompareReport {
+        let mut output = OutputReport::new(0, "conv", OutputStatus::Ok);
+        output.shape = Some(vec![1, 2]);
+        output.reference_shape = Some(vec![1, 2]);
+        output.max_abs_diff = Some(0.25);
+        output.max_rel_diff = Some(0.5);
+        output.worst = Some(vec![0, 1]);
+        let mut node = NodeReport {
+            id: 1,
+            name: "conv".to_string(),
+            status: NodeStatus::Passed,
+            outputs: vec![],
+            inputs: vec!["input value #0: 1,2,F32 0, 0".to_string()],
+        };
+        if !passed {
+            output.status = OutputStatus::Mismatch;
+            output.message = Some("Mismatch at [0, 1]".to_string());
+            output.found = Some(rctensor2(&[[0f32, 1.0]]));
+            output.reference = Some(rctensor2(&[[0f32, 1.25]]));
+            node.status = NodeStatus::Failed;
+        }
+        node.outputs.push(output);
+        CompareReport {
+            passed,
+            nodes_passed: passed as usize,
+            nodes_failed: !passed as usize,
+            nodes_skipped: 0,
+            nodes: vec![
+                NodeReport {
+                    id: 0,
+                    name: "input".to_string(),
+                    status: NodeStatus::Input,
+                    outputs: vec![],
+                    inputs: vec![],
+                },
+                node,
+            ],
+        }
+    }
+
+    fn tolerances(args: &[&str]) -> Tolerances {
+        let app = crate::compare_options(clap::App::new("compare"));
+        let matches = app.get_matches_from(std::iter::once("compare").chain(args.iter().cloned()));
+        Tolerances::from_clap(&matches).unwrap()
+    }
+
+    #[test]
+    fn quantized_outputs_are_compared_dequantized() {
+        let found = rctensor1(&[10u8, 21, 30]);
+        let reference = rctensor1(&[10u8, 20, 30]);
+        let raw = OutputReport::compare(0, "q", &found, &reference, CompareOptions::default());
+        assert_eq!(raw.status, OutputStatus::Mismatch);
+        let tolerances = tolerances(&["--quantization", "q=1e-4,128"]);
+        assert_eq!(tolerances.default.quantization, None);
+        let options = tolerances.for_output("q", "q");
+        assert_eq!(options.quantization, Some((1e-4, 128)));
+        let dequant = OutputReport::compare(0, "q", &found, &reference, options);
+        assert_eq!(dequant.status, OutputStatus::Ok);
+        assert!((dequant.max_abs_diff.unwrap() - 1e-4).abs() < 1e-6);
+        let coarse = CompareOptions { quantization: Some((1., 128)), ..options };
+        let coarse = OutputReport::compare(0, "q", &found, &reference, coarse);
+        assert_eq!(coarse.status, OutputStatus::Mismatch);
+    }
+
+    fn add_model(value: f32) -> TypedModel {
+        let mut model = TypedModel::default();
+        let input =
+            model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[3])).unwrap();
+        let output = model
+            .wire_node("add", tract_core::ops::math::add::unary(rctensor1(&[value])), &[input])
+            .unwrap();
+        model.set_output_outlets(&output).unwrap();
+        model
+    }
+
+    #[test]
+    fn tract_against_itself() {
+        let model = add_model(1.);
+        let inputs = [rctensor1(&[0f32, 1., 2.])];
+        let report = compare_executors(
+            &mut TractExecutor(&model),
+            &mut TractExecutor(&model),
+            &model,
+            &inputs,
+            &[],
+            &Tolerances::default(),
+            false,
+        )
+        .unwrap();
+        assert!(report.passed);
+        assert_eq!(report.nodes_passed, 1);
+        assert_eq!(report.nodes[0].name, "add");
+        assert_eq!(report.nodes[0].outputs[0].max_abs_diff, Some(0.));
+
+        let other = add_model(1.5);
+        let report = compare_executors(
+            &mut TractExecutor(&model),
+            &mut TractExecutor(&other),
+            &model,
+            &inputs,
+            &["input".to_string(), "add".to_string()],
+            &Tolerances::default(),
+            false,
+        )
+        .unwrap();
+        assert!(!report.passed);
+        assert_eq!((report.nodes_passed, report.nodes_failed), (1, 1));
+        assert_eq!(report.nodes[1].status, NodeStatus::Failed);
+        assert_eq!(report.nodes[1].outputs[0].max_abs_diff, Some(0.5));
+    }
+
+    #[test]
+    fn tolerance_per_output() {
+        let mut model = TypedModel::default();
+        let input =
+            model.add_source("input", TypedFact::dt_shape(f32::datum_type(), &[2])).unwrap();
+        let a = model
+            .wire_node("a", tract_core::ops::math::add::unary(rctensor1(&[1f32])), &[input])
+            .unwrap();
+        let b = model
+            .wire_node("b", tract_core::ops::math::mul::unary(rctensor1(&[2f32])), &[input])
+            .unwrap();
+        model.set_outlet_label(a[0], "a_out".to_string()).unwrap();
+        model.set_output_outlets(&[a[0], b[0]]).unwrap();
+        let mut values: HashMap<String, CliResult<Arc<Tensor>>> = HashMap::new();
+        values.insert("input".to_string(), Ok(rctensor1(&[1f32, 2.])));
+        values.insert("a_out".to_string(), Ok(rctensor1(&[2.05f32, 3.])));
+        values.insert("b".to_string(), Ok(rctensor1(&[2f32, 4.5])));
+        let failed = |args: &[&str]| {
+            let report = compare_report(true, false, &model, &values, &tolerances(args)).unwrap();
+            report
+                .nodes
+                .iter()
+                .filter(|n| n.is_failure())
+                .map(|n| n.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(failed(&[]), vec!["a", "b"]);
+        assert_eq!(
+            failed(&["--tolerance", "a_out=0.1", "--tolerance", "b=1"]),
+            Vec::<String>::new()
+        );
+        // a node name applies to its labelled outlet too
+        assert_eq!(failed(&["--tolerance", "a=0.1", "--tolerance", "b=1"]), Vec::<String>::new());
+        assert_eq!(failed(&["--tolerance", "a=0.1", "--tolerance", "b=0.1"]), vec!["b"]);
+        assert_eq!(failed(&["--tolerance", "a=0.01", "--tolerance", "b=1"]), vec!["a"]);
+        // the label takes precedence over the node name
+        assert_eq!(
+            failed(&["--tolerance", "a_out=0.01", "--tolerance", "a=1", "--atol", "1"]),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn unsupported_output_is_skipped() {
+        use tract_hir::internal::*;
+        let mut model = InferenceModel::default();
+        let input = model
+            .add_source("input", InferenceFact::dt_shape(f32::datum_type(), tvec!(2)))
+            .unwrap();
+        let a = model.wire_node("a", tract_core::ops::math::neg(), &[input]).unwrap();
+        let unsupported = model
+            .wire_node("u", tract_core::ops::unimpl::UnimplementedOp::new(1, "Foo", ""), &[input])
+            .unwrap();
+        let b = model.wire_node("b", tract_core::ops::math::neg(), &unsupported).unwrap();
+        model.set_output_outlets(&[a[0], b[0]]).unwrap();
+        let mut values: HashMap<String, CliResult<Arc<Tensor>>> = HashMap::new();
+        values.insert("input".to_string(), Ok(rctensor1(&[1f32, 2.])));
+        values.insert("a".to_string(), Ok(rctensor1(&[-1f32, -2.])));
+        values.insert("b".to_string(), Ok(rctensor1(&[1f32, 2.])));
+
+        let report = compare_report(true, true, &model, &values, &Tolerances::default()).unwrap();
+        assert!(report.passed);
+        assert_eq!((report.nodes_passed, report.nodes_failed), (1, 0));
+        let status =
+            |name: &str| report.nodes.iter().find(|n| n.name == name).unwrap().status.clone();
+        assert_eq!(status("a"), NodeStatus::Passed);
+        assert_eq!(status("u"), NodeStatus::Skipped("unsupported in tract: Foo".to_string()));
+        assert_eq!(status("b"), NodeStatus::Skipped("depends on skipped node u".to_string()));
+
+        let report = compare_report(true, false, &model, &values, &Tolerances::default()).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.nodes_skipped, 0);
+    }
+
+    #[test]
+    fn diff_csv_is_sorted() {
+        let found = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let reference = tensor2(&[[1.5f32, 2.], [0., 4.25]]);
+        let mut csv = vec![];
+        write_diff_csv(&mut csv, "out", &found, &reference, 3).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let rows = csv
+            .lines()
+            .map(|line| {
+                let (label, coord, rest) = line.splitn(3, "\",").collect_tuple().unwrap();
+                let values = rest.split(',').map(|v| v.parse::<f64>().unwrap()).collect::<Vec<_>>();
+                (label.trim_start_matches('"').to_string(), coord.to_string(), values)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|r| r.0 == "out"));
+        assert_eq!(
+            rows.iter().map(|r| &*r.1).collect::<Vec<_>>(),
+            vec!["\"[1, 0]", "\"[0, 0]", "\"[1, 1]"]
+        );
+        // zero reference: the relative difference uses the smallest positive f64
+        assert_eq!(rows[0].2, vec![3., 0., 3., 3. / std::f64::MIN_POSITIVE]);
+        assert_eq!(rows[1].2, vec![1., 1.5, 0.5, 0.5 / 1.5]);
+        assert!(rows.windows(2).all(|w| w[0].2[2] >= w[1].2[2]));
+    }
+
+    #[test]
+    fn common_prefix_on_dynamic_axis() {
+        let found =
+            Tensor::from(tract_ndarray::Array2::from_shape_fn((5, 4), |(i, j)| (i * 4 + j) as f32));
+        let reference =
+            Tensor::from(tract_ndarray::Array2::from_shape_fn((7, 4), |(i, j)| (i * 4 + j) as f32));
+        let (f, r) = common_prefix(&found, &reference, 0).unwrap();
+        assert_eq!(f.shape(), &[5, 4]);
+        assert_eq!(f, found);
+        assert_eq!(r, found);
+        assert!(common_prefix(&found, &reference, 1).is_err());
+        assert!(common_prefix(&found, &reference, 2).is_err());
+
+        let report = OutputReport::compare(
+            0,
+            "boxes",
+            &found.clone().into_arc_tensor(),
+            &reference.into_arc_tensor(),
+            CompareOptions { dynamic_axis: Some(0), ..CompareOptions::default() },
+        );
+        assert_eq!(report.status, OutputStatus::Ok);
+        assert_eq!(report.dynamic_lengths, Some((5, 7)));
+    }
+
+    #[test]
+    fn dynamic_axis_parsing() {
+        let t = tolerances(&["--dynamic-axis", "boxes=0", "--dynamic-axis", "1"]);
+        assert_eq!(t.default.dynamic_axis, Some(1));
+        assert_eq!(t.for_output("boxes", "boxes").dynamic_axis, Some(0));
+        assert_eq!(t.for_output("scores", "scores").dynamic_axis, Some(1));
+        let t = tolerances(&["--dynamic-axis", "0", "--tolerance", "boxes=0.1"]);
+        assert_eq!(t.for_output("boxes", "boxes").dynamic_axis, Some(0));
+        assert_eq!(t.for_output("boxes", "boxes").atol, 0.1);
+        let app = crate::compare_options(clap::App::new("compare"));
+        let matches = app.get_matches_from(vec!["compare", "--dynamic-axis", "boxes=x"]);
+        assert!(Tolerances::from_clap(&matches).is_err());
+    }
+
+    #[test]
+    fn report_json_round_trip() {
+        for &passed in &[true, false] {
+            let report = report(passed);
+            let json = serde_json::to_string(&report).unwrap();
+            let back: CompareReport = serde_json::from_str(&json).unwrap();
+            assert_eq!(serde_json::to_string(&back).unwrap(), json);
+            assert_eq!(back.passed, passed);
+            assert_eq!(back.nodes_failed, !passed as usize);
+            assert_eq!(back.nodes[0].status, NodeStatus::Input);
+            let node = &back.nodes[1];
+            assert_eq!(node.status, if passed { NodeStatus::Passed } else { NodeStatus::Failed });
+            assert!(node.inputs.is_empty());
+            let output = &node.outputs[0];
+            assert_eq!(output.status, report.nodes[1].outputs[0].status);
+            assert_eq!(output.message, report.nodes[1].outputs[0].message);
+            assert_eq!(output.worst, Some(vec![0, 1]));
+            assert!(output.found.is_none() && output.reference.is_none());
+        }
+    }
+}
@@ -34,6 +34,9 @@ pub struct DisplayParams {
     pub json: bool,
     pub info: bool,
     pub left_column_width: usize,
+    pub dump_limit: usize,
+    pub diff_csv: Option<String>,
+    pub diff_csv_top_k: usize,
 }
 
 impl DisplayParams {
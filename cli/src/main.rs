@@ -25,6 +25,7 @@ mod draw;
 mod dump;
 mod errors {}
 mod export;
+mod manifest;
 mod model;
 mod params;
 mod profile;
@@ -177,7 +178,7 @@ fn main() -> tract_core::anyhow::Result<()> {
                 .takes_value(false)
                 .help("Try nodes one per one to mitigate crashes"),
         );
-    app = app.subcommand(output_options(compare));
+    app = app.subcommand(compare_options(output_options(compare)));
 
     let compare_npz = clap::SubCommand::with_name("compare-npz")
         .long_about("Compares the output of tract to a refrence npz file.")
@@ -188,7 +189,7 @@ fn main() -> tract_core::anyhow::Result<()> {
                 .help("Do not reset with reference values at each node"),
         )
         .arg(Arg::with_name("npz").takes_value(true).required(true).help("Npz filename"));
-    app = app.subcommand(output_options(compare_npz));
+    app = app.subcommand(compare_options(output_options(compare_npz)));
 
     let compare_pbdir = clap::SubCommand::with_name("compare-pbdir")
         .long_about(
@@ -201,12 +202,30 @@ fn main() -> tract_core::anyhow::Result<()> {
                 .help("Do not reset with reference values at each node"),
         )
         .arg(Arg::with_name("pbdir").takes_value(true).required(true).help("protobuf dir"));
-    app = app.subcommand(output_options(compare_pbdir));
+    app = app.subcommand(compare_options(output_options(compare_pbdir)));
+
+    let compare_manifest = clap::SubCommand::with_name("compare-manifest")
+        .long_about("Runs the conformance cases of a json manifest against their expected outputs.")
+        .arg(Arg::with_name("manifest").takes_value(true).required(true).help("Manifest filename"))
+        .arg(Arg::with_name("json").long("json").help("dump the report as json"));
+    app = app.subcommand(compare_manifest);
 
     let bench = clap::SubCommand::with_name("bench")
         .long_about("Benchmarks tract on randomly generated input.");
     let bench = output_options(bench);
-    let bench = benchlimits_options(bench);
+    let bench = benchlimits_options(bench)
+        .arg(
+            Arg::with_name("warmup")
+                .long("warmup")
+                .takes_value(true)
+                .help("Untimed iterations to run before measuring [default: 0]"),
+        )
+        .arg(
+            Arg::with_name("steady")
+                .long("steady")
+                .takes_value(true)
+                .help("Time this many iterations one by one, reporting min/median/max"),
+        );
     app = app.subcommand(bench);
 
     let criterion = clap::SubCommand::with_name("criterion")
@@ -282,6 +301,12 @@ fn main() -> tract_core::anyhow::Result<()> {
         .long_about("Run the graph")
         .arg(Arg::with_name("dump").long("dump").help("Show output"))
         .arg(Arg::with_name("steps").long("steps").help("Show all inputs and outputs"))
+        .arg(
+            Arg::with_name("save-values")
+                .takes_value(true)
+                .long("save-values")
+                .help("Save all node values to a npz file, to be used with compare-npz"),
+        )
         .arg(
             Arg::with_name("assert-sane-floats")
                 .long("assert-sane-floats")
@@ -375,6 +400,71 @@ fn benchlimits_options<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b>
         )
 }
 
+fn compare_options<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
+    use clap::*;
+    command
+        .arg(
+            Arg::with_name("dump-limit")
+                .long("dump-limit")
+                .takes_value(true)
+                .help("Max number of elements to print for mismatching tensors [default: 256]"),
+        )
+        .arg(
+            Arg::with_name("diff-csv")
+                .long("diff-csv")
+                .takes_value(true)
+                .help("Write the worst differences of mismatching outputs to a csv file"),
+        )
+        .arg(
+            Arg::with_name("diff-csv-top-k")
+                .long("diff-csv-top-k")
+                .takes_value(true)
+                .help("Number of differences to write per mismatching output [default: 100]"),
+        )
+        .arg(
+            Arg::with_name("atol")
+                .long("atol")
+                .takes_value(true)
+                .help("Absolute tolerance for comparing values [default: 5e-4]"),
+        )
+        .arg(
+            Arg::with_name("rtol")
+                .long("rtol")
+                .takes_value(true)
+                .help("Relative tolerance for comparing values [default: 1e-4]"),
+        )
+        .arg(
+            Arg::with_name("tolerance")
+                .long("tolerance")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Override tolerances for one output (name=atol[,rtol])"),
+        )
+        .arg(
+            Arg::with_name("dynamic-axis")
+                .long("dynamic-axis")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Allow outputs to differ in length on an axis ([name=]axis), comparing the common prefix"),
+        )
+        .arg(
+            Arg::with_name("quantization")
+                .long("quantization")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Dequantize integer outputs before comparing ([name=]scale,zero_point)"),
+        )
+        .arg(
+            Arg::with_name("skip-unsupported")
+                .long("skip-unsupported")
+                .takes_value(false)
+                .help("Report nodes unsupported by tract or by the reference as skipped instead of failing"),
+        )
+}
+
 fn output_options<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
     use clap::*;
     command
@@ -413,7 +503,7 @@ fn output_options<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
         .arg(Arg::with_name("info").long("info").help("show op inner information"))
         .arg(Arg::with_name("io-long").long("io-long").help("show full i/o information"))
         .arg(Arg::with_name("io-none").long("io-none").help("hide i/o information"))
-        .arg(Arg::with_name("json").long("json").help("dump performance info as json"))
+        .arg(Arg::with_name("json").long("json").help("dump perf info or comparison as json"))
         .arg(Arg::with_name("outlet-labels").long("outlet-labels").help("display outlet labels"))
         .arg(
             Arg::with_name("invariants")
@@ -445,6 +535,10 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
         return Ok(());
     }
 
+    if let ("compare-manifest", Some(m)) = matches.subcommand() {
+        return manifest::handle(m.value_of("manifest").unwrap(), m.is_present("json"));
+    }
+
     let builder_result = Parameters::from_clap(&matches, probe);
     #[allow(unused_mut)]
     let mut params = match builder_result {
@@ -475,7 +569,11 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
     match matches.subcommand() {
         ("bench", Some(m)) => {
             need_optimisations = true;
-            bench::handle(&params, &BenchLimits::from_clap(&m)?, probe)
+            if let Some(steady) = m.value_of("steady") {
+                bench::steady_state(&params, &BenchLimits::from_clap(&m)?, steady.parse()?)
+            } else {
+                bench::handle(&params, &BenchLimits::from_clap(&m)?, probe)
+            }
         }
 
         ("criterion", _) => {
@@ -487,30 +585,38 @@ fn handle(matches: clap::ArgMatches, probe: Option<&Probe>) -> CliResult<()> {
         ("compare", Some(m)) => compare::handle_tensorflow(
             m.is_present("cumulative"),
             m.is_present("resilient"),
+            m.is_present("skip-unsupported"),
             &mut params,
             display_params_from_clap(&matches, m)?,
+            &compare::Tolerances::from_clap(m)?,
         ),
 
         #[cfg(not(feature = "conform"))]
         ("compare", Some(m)) => compare::handle_reference_stage(
             m.is_present("cumulative"),
+            m.is_present("skip-unsupported"),
             &params,
             &display_params_from_clap(&matches, m)?,
+            &compare::Tolerances::from_clap(m)?,
         ),
 
         ("compare-npz", Some(m)) => compare::handle_npz(
             m.is_present("cumulative"),
+            m.is_present("skip-unsupported"),
             m.value_of("npz").unwrap(),
             &params,
             &display_params_from_clap(&matches, m)?,
+            &compare::Tolerances::from_clap(m)?,
         ),
 
         #[cfg(feature = "onnx")]
         ("compare-pbdir", Some(m)) => compare::handle_pbdir(
             m.is_present("cumulative"),
+            m.is_present("skip-unsupported"),
             m.value_of("pbdir").unwrap(),
             &params,
             &display_params_from_clap(&matches, m)?,
+            &compare::Tolerances::from_clap(m)?,
         ),
 
         ("run", Some(m)) => run::handle(&params, m),
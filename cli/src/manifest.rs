@@ -0,0 +1,297 @@
+use std::path::{Path, PathBuf};
+
+use ansi_term::Color::*;
+use tract_hir::internal::*;
+
+use crate::CliResult;
+
+/// One conformance case, as found in a manifest file.
+///
+/// Paths are relative to the manifest directory. Without a `tolerance`, outputs
+/// are checked with `Tensor::close_enough`, otherwise the max absolute
+/// difference must not exceed it.
+///
+/// A TensorFlow model without expected `outputs` is compared node by node
+/// against TensorFlow itself (needs the `conform` feature).
+#[derive(Clone, Debug, Deserialize)]
+pub struct ManifestCase {
+    pub name: Option<String>,
+    pub model: PathBuf,
+    pub inputs: Vec<PathBuf>,
+    #[serde(default)]
+    pub outputs: Vec<PathBuf>,
+    pub tolerance: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ManifestReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub cases: Vec<CaseReport>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CaseReport {
+    pub name: String,
+    pub status: CaseStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    Passed,
+    Failed(String),
+    Error(String),
+}
+
+pub fn handle(manifest: &str, json: bool) -> CliResult<()> {
+    let report = run_manifest(manifest)?;
+    if json {
+        serde_json::to_writer(std::io::stdout(), &report)?;
+    } else {
+        for case in &report.cases {
+            match &case.status {
+                CaseStatus::Passed => println!("{} {}", Green.paint("PASS"), case.name),
+                CaseStatus::Failed(e) => {
+                    println!("{} {}: {}", Red.bold().paint("FAIL"), case.name, e)
+                }
+                CaseStatus::Error(e) => {
+                    println!("{} {}: {}", Red.bold().paint("ERROR"), case.name, e)
+                }
+            }
+        }
+    }
+    if report.failed > 0 {
+        bail!("{} case(s) failed out of {}.", report.failed, report.cases.len())
+    }
+    Ok(())
+}
+
+/// Run every case of a json manifest (`{ "cases": [ ... ] }`).
+///
+/// Only an unreadable manifest is an error: a malformed or failing case is
+/// reported as such and the remaining cases are still run.
+pub fn run_manifest(path: impl AsRef<Path>) -> CliResult<ManifestReport> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let manifest: serde_json::Value = serde_json::from_reader(
+        std::fs::File::open(path).with_context(|| format!("Opening manifest {:?}", path))?,
+    )
+    .with_context(|| format!("Parsing manifest {:?}", path))?;
+    let cases = manifest
+        .get("cases")
+        .and_then(|c| c.as_array())
+        .with_context(|| format!("Expected a \"cases\" array in {:?}", path))?;
+    let mut report = ManifestReport::default();
+    for (ix, case) in cases.iter().enumerate() {
+        let (name, status) = match serde_json::from_value::<ManifestCase>(case.clone()) {
+            Ok(case) => {
+                let name = case.name.clone().unwrap_or_else(|| format!("case #{}", ix));
+                let status = match run_case(dir, &case) {
+                    Ok(None) => CaseStatus::Passed,
+                    Ok(Some(failure)) => CaseStatus::Failed(failure),
+                    Err(e) => CaseStatus::Error(format!("{:#}", e)),
+                };
+                (name, status)
+            }
+            Err(e) => {
+                (format!("case #{}", ix), CaseStatus::Error(format!("Malformed entry: {}", e)))
+            }
+        };
+        if status == CaseStatus::Passed {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.cases.push(CaseReport { name, status });
+    }
+    Ok(report)
+}
+
+/// Run a case, returning a description of the mismatch if there is one.
+fn run_case(dir: &Path, case: &ManifestCase) -> CliResult<Option<String>> {
+    let inputs = case
+        .inputs
+        .iter()
+        .map(|p| crate::tensor::for_npy(dir.join(p)))
+        .collect::<CliResult<TVec<Tensor>>>()?;
+    let expected = case
+        .outputs
+        .iter()
+        .map(|p| crate::tensor::for_npy(dir.join(p)))
+        .collect::<CliResult<Vec<Tensor>>>()?;
+    let model = load_model(&dir.join(&case.model), &inputs)?;
+    if expected.is_empty() {
+        return run_tensorflow_case(&dir.join(&case.model), &model, inputs, case.tolerance);
+    }
+    let found = SimplePlan::new(&model)?.run(inputs)?;
+    if found.len() != expected.len() {
+        return Ok(Some(format!(
+            "Expected {} output(s), model produced {}",
+            expected.len(),
+            found.len()
+        )));
+    }
+    for (ix, (found, expected)) in found.iter().zip(expected.iter()).enumerate() {
+        if let Some(tolerance) = case.tolerance {
+            match crate::compare::diff_stats(found, expected) {
+                None => {
+                    return Ok(Some(format!(
+                        "Output #{}: can not compare {:?} to {:?}",
+                        ix, found, expected
+                    )))
+                }
+                Some((abs, _, worst)) if !(abs <= tolerance) => {
+                    return Ok(Some(format!(
+                        "Output #{}: max abs diff {} at {:?} exceeds {}",
+                        ix, abs, worst, tolerance
+                    )))
+                }
+                _ => (),
+            }
+        } else if let Err(e) = found.close_enough(expected, true) {
+            return Ok(Some(format!("Output #{}: {}", ix, e)));
+        }
+    }
+    Ok(None)
+}
+
+/// Compare a TensorFlow model running in tract against TensorFlow itself.
+#[cfg(feature = "conform")]
+fn run_tensorflow_case(
+    path: &Path,
+    model: &TypedModel,
+    inputs: TVec<Tensor>,
+    tolerance: Option<f32>,
+) -> CliResult<Option<String>> {
+    if !is_tensorflow(path) {
+        bail!("Expected outputs are required, unless the model is a TensorFlow graph")
+    }
+    let mut tolerances = crate::compare::Tolerances::default();
+    if let Some(tolerance) = tolerance {
+        tolerances.default.atol = tolerance;
+        tolerances.default.rtol = 0.0;
+    }
+    let tf = tract_tensorflow::conform::tf::for_path(path)?;
+    let inputs = inputs.into_iter().map(|t| t.into_arc_tensor()).collect::<Vec<_>>();
+    let report = crate::compare::compare_tensorflow(tf, model, &inputs, &[], &tolerances, false)?;
+    let failures = report
+        .nodes
+        .iter()
+        .filter(|node| node.is_failure())
+        .map(|node| match &node.status {
+            crate::compare::NodeStatus::Error(e) => format!("{}: {}", node.name, e),
+            _ => {
+                let messages =
+                    node.outputs.iter().filter_map(|o| o.message.as_deref()).collect::<Vec<_>>();
+                format!("{}: {}", node.name, messages.join(", "))
+            }
+        })
+        .collect::<Vec<_>>();
+    if failures.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(format!("Mismatch against TensorFlow: {}", failures.join("; "))))
+    }
+}
+
+#[cfg(not(feature = "conform"))]
+fn run_tensorflow_case(
+    path: &Path,
+    _model: &TypedModel,
+    _inputs: TVec<Tensor>,
+    _tolerance: Option<f32>,
+) -> CliResult<Option<String>> {
+    if !is_tensorflow(path) {
+        bail!("Expected outputs are required, unless the model is a TensorFlow graph")
+    }
+    bail!("Comparing against TensorFlow needs tract built with the conform feature")
+}
+
+fn is_nnef(path: &Path) -> bool {
+    path.is_dir()
+        || path.to_string_lossy().ends_with(".tar")
+        || path.to_string_lossy().ends_with(".tar.gz")
+        || path.extension().map(|s| s == "tgz").unwrap_or(false)
+}
+
+fn is_tensorflow(path: &Path) -> bool {
+    !is_nnef(path) && path.extension().map(|s| s != "onnx").unwrap_or(true)
+}
+
+fn load_model(path: &Path, inputs: &[Tensor]) -> CliResult<TypedModel> {
+    if is_nnef(path) {
+        return Ok(tract_nnef::nnef().with_tract_core().model_for_path(path)?.into_optimized()?);
+    }
+    let mut model: InferenceModel = if path.extension().map(|s| s == "onnx").unwrap_or(false) {
+        #[cfg(feature = "onnx")]
+        {
+            tract_onnx::onnx().model_for_path(path)?
+        }
+        #[cfg(not(feature = "onnx"))]
+        {
+            bail!("tract is build without ONNX support")
+        }
+    } else {
+        #[cfg(feature = "tf")]
+        {
+            tract_tensorflow::tensorflow().model_for_path(path)?
+        }
+        #[cfg(not(feature = "tf"))]
+        {
+            bail!("tract is build without TensorFlow support")
+        }
+    };
+    for (ix, input) in inputs.iter().enumerate() {
+        model.set_input_fact(ix, InferenceFact::dt_shape(input.datum_type(), input.shape()))?;
+    }
+    Ok(model.into_optimized()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn two_cases_one_failing() {
+        let dir = std::env::temp_dir().join(format!("tract-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("double")).unwrap();
+        std::fs::write(
+            dir.join("double/graph.nnef"),
+            "version 1.0;\n\
+             graph double(input) -> (output) {\n\
+             input = external(shape = [3]);\n\
+             output = add(input, input);\n\
+             }\n",
+        )
+        .unwrap();
+        let npy = |name: &str, values: &[f32]| {
+            let array = tract_ndarray::arr1(values);
+            ndarray_npy::write_npy(dir.join(name), &array).unwrap();
+        };
+        npy("input.npy", &[1., 2., 3.]);
+        npy("good.npy", &[2., 4., 6.]);
+        npy("bad.npy", &[2., 4., 7.]);
+        std::fs::write(
+            dir.join("manifest.json"),
+            r#"{ "cases": [
+                { "name": "pass", "model": "double", "inputs": [ "input.npy" ],
+                  "outputs": [ "good.npy" ] },
+                { "name": "fail", "model": "double", "inputs": [ "input.npy" ],
+                  "outputs": [ "bad.npy" ], "tolerance": 0.5 }
+            ] }"#,
+        )
+        .unwrap();
+        let report = run_manifest(dir.join("manifest.json"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let report = report.unwrap();
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert_eq!(report.cases[0].name, "pass");
+        assert_eq!(report.cases[0].status, CaseStatus::Passed);
+        assert_eq!(report.cases[1].name, "fail");
+        match &report.cases[1].status {
+            CaseStatus::Failed(msg) => assert!(msg.contains("max abs diff 1"), "{}", msg),
+            status => panic!("Expected a failure, got {:?}", status),
+        }
+    }
+}
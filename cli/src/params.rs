@@ -719,6 +719,7 @@ impl Parameters {
 pub struct BenchLimits {
     pub max_iters: usize,
     pub max_time: std::time::Duration,
+    pub warmup: usize,
 }
 
 impl BenchLimits {
@@ -731,7 +732,8 @@ impl BenchLimits {
             .transpose()?
             .map(std::time::Duration::from_millis)
             .unwrap_or(std::time::Duration::from_secs(5));
-        Ok(BenchLimits { max_iters, max_time })
+        let warmup = matches.value_of("warmup").map(usize::from_str).transpose()?.unwrap_or(0);
+        Ok(BenchLimits { max_iters, max_time, warmup })
     }
 }
 
@@ -766,6 +768,13 @@ pub fn display_params_from_clap(
         },
         info: matches.is_present("info"),
         json: matches.is_present("json"),
+        dump_limit: matches.value_of("dump-limit").map(|s| s.parse()).transpose()?.unwrap_or(256),
+        diff_csv: matches.value_of("diff-csv").map(String::from),
+        diff_csv_top_k: matches
+            .value_of("diff-csv-top-k")
+            .map(|s| s.parse())
+            .transpose()?
+            .unwrap_or(100),
     })
 }
 
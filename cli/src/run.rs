@@ -52,11 +52,13 @@ fn run_regular(
 ) -> CliResult<TVec<Arc<Tensor>>> {
     let steps = options.is_present("steps");
     let assert_sane_floats = options.is_present("assert-sane-floats");
+    let save_values = options.value_of("save-values");
     let inputs = crate::tensor::retrieve_or_make_inputs(tract, params)?;
-    dispatch_model!(tract, |m| {
+    let mut values: Vec<(String, Arc<Tensor>)> = vec![];
+    let outputs = dispatch_model!(tract, |m| {
         let plan = SimplePlan::new(m)?;
         let mut state = SimpleState::new(plan)?;
-        Ok(state.run_plan_with_eval(inputs, |session_state, state, node, input| {
+        state.run_plan_with_eval(inputs, |session_state, state, node, input| {
             if steps {
                 for i in &input {
                     eprintln!(
@@ -93,9 +95,28 @@ fn run_regular(
                     }
                 }
             }
+            if save_values.is_some() {
+                if let Some(v) = r.get(0) {
+                    values.push((node.name.clone(), v.clone()));
+                }
+                for (slot, v) in r.iter().enumerate() {
+                    if let Some(label) = tract.outlet_label((node.id, slot).into()) {
+                        if label != node.name {
+                            values.push((label.to_string(), v.clone()));
+                        }
+                    }
+                }
+            }
             Ok(r)
-        })?)
-    })
+        })
+    })?;
+    if let Some(path) = save_values {
+        let mut npz = ndarray_npy::NpzWriter::new(std::fs::File::create(path)?);
+        for (name, value) in &values {
+            crate::tensor::write_npz(&mut npz, name, value)?;
+        }
+    }
+    Ok(outputs)
 }
 
 #[cfg(feature = "pulse")]
@@ -222,6 +222,52 @@ pub fn for_npz(npz: &mut ndarray_npy::NpzReader<fs::File>, name: &str) -> CliRes
     bail!("Can not extract tensor from {}", name);
 }
 
+pub fn write_npz(
+    npz: &mut ndarray_npy::NpzWriter<fs::File>,
+    name: &str,
+    tensor: &Tensor,
+) -> CliResult<()> {
+    fn write_t<T: Datum + ndarray_npy::WritableElement>(
+        npz: &mut ndarray_npy::NpzWriter<fs::File>,
+        name: &str,
+        tensor: &Tensor,
+    ) -> CliResult<()> {
+        npz.add_array(format!("{}.npy", name), &tensor.to_array_view::<T>()?)
+            .with_context(|| format!("Writing {} to npz", name))?;
+        Ok(())
+    }
+    match tensor.datum_type() {
+        DatumType::Bool => write_t::<bool>(npz, name, tensor),
+        DatumType::U8 => write_t::<u8>(npz, name, tensor),
+        DatumType::U16 => write_t::<u16>(npz, name, tensor),
+        DatumType::U32 => write_t::<u32>(npz, name, tensor),
+        DatumType::U64 => write_t::<u64>(npz, name, tensor),
+        DatumType::I8 => write_t::<i8>(npz, name, tensor),
+        DatumType::I16 => write_t::<i16>(npz, name, tensor),
+        DatumType::I32 => write_t::<i32>(npz, name, tensor),
+        DatumType::I64 => write_t::<i64>(npz, name, tensor),
+        DatumType::F16 => write_t::<f32>(npz, name, &*tensor.cast_to::<f32>()?),
+        DatumType::F32 => write_t::<f32>(npz, name, tensor),
+        DatumType::F64 => write_t::<f64>(npz, name, tensor),
+        dt => bail!("Can not write {:?} to npz", dt),
+    }
+}
+
+pub fn for_npy(filename: impl AsRef<std::path::Path>) -> CliResult<Tensor> {
+    let filename = filename.as_ref();
+    macro_rules! try_dt {
+        ($($t:ty),*) => {
+            $(
+                if let Ok(t) = ndarray_npy::read_npy::<_, tract_ndarray::ArrayD<$t>>(filename) {
+                    return Ok(t.into_tensor());
+                }
+            )*
+        };
+    }
+    try_dt!(f32, f64, i8, i16, i32, i64, u8, u16, u32, u64);
+    bail!("Can not extract tensor from {:?}", filename);
+}
+
 pub fn for_string(value: &str) -> CliResult<(Option<String>, InferenceFact)> {
     if value.starts_with("@") {
         for_data(&value[1..])
@@ -131,46 +131,57 @@ impl Reducer {
     }
 }
 
+// NaN handling: Max and Min propagate NaN (any NaN in the reduced axis gives
+// NaN), ArgMax and ArgMin return the index of the first NaN, whatever `last`.
+#[allow(clippy::eq_op)]
+fn is_nan<T: PartialOrd>(v: &T) -> bool {
+    v != v
+}
+
 fn argmax_t<'a, T>(v: ArrayViewD<'a, T>, last: bool) -> i64
 where
     T: Copy + Datum + num_traits::Bounded + ::std::cmp::PartialOrd,
 {
-    v.iter()
-        .copied()
-        .enumerate()
-        .fold(
-            (0usize, T::min_value()),
-            |acc, v| if v.1 > acc.1 || (last && acc.1 == v.1) { v } else { acc },
-        )
-        .0 as i64
+    let mut best = (0usize, T::min_value());
+    for v in v.iter().copied().enumerate() {
+        if is_nan(&v.1) {
+            return v.0 as i64;
+        }
+        if v.1 > best.1 || (last && best.1 == v.1) {
+            best = v;
+        }
+    }
+    best.0 as i64
 }
 
 fn argmin_t<'a, T>(v: ArrayViewD<'a, T>, last: bool) -> i64
 where
     T: Copy + Datum + num_traits::Bounded + ::std::cmp::PartialOrd,
 {
-    v.iter()
-        .copied()
-        .enumerate()
-        .fold(
-            (0usize, T::max_value()),
-            |acc, v| if v.1 < acc.1 || (last && acc.1 == v.1) { v } else { acc },
-        )
-        .0 as i64
+    let mut best = (0usize, T::max_value());
+    for v in v.iter().copied().enumerate() {
+        if is_nan(&v.1) {
+            return v.0 as i64;
+        }
+        if v.1 < best.1 || (last && best.1 == v.1) {
+            best = v;
+        }
+    }
+    best.0 as i64
 }
 
 fn max_t<'a, T>(v: ArrayViewD<'a, T>, _last: bool) -> T
 where
     T: Copy + Datum + num_traits::Bounded + ::std::cmp::PartialOrd,
 {
-    v.fold(T::min_value(), |acc, &v| if acc > v { acc } else { v })
+    v.fold(T::min_value(), |acc, &v| if is_nan(&acc) || acc > v { acc } else { v })
 }
 
 fn min_t<'a, T>(v: ArrayViewD<'a, T>, _last: bool) -> T
 where
     T: Copy + Datum + num_traits::Bounded + ::std::cmp::PartialOrd,
 {
-    v.fold(T::max_value(), |acc, &v| if acc < v { acc } else { v })
+    v.fold(T::max_value(), |acc, &v| if is_nan(&acc) || acc < v { acc } else { v })
 }
 
 fn prod_t<'a, T>(v: ArrayViewD<'a, T>, _last: bool) -> T
@@ -253,3 +264,29 @@ impl TypedOp for Reduce {
         Ok(Some(AxisChangeConsequence::new(model, node, op, change)))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn max_min_propagate_nan() {
+        let t = tensor2(&[[1f32, std::f32::NAN, 3.], [4., 5., 6.]]);
+        let max = Reducer::Max.reduce(&[1], &t).unwrap();
+        let max = max.as_slice::<f32>().unwrap();
+        assert!(max[0].is_nan());
+        assert_eq!(max[1], 6.);
+        let min = Reducer::Min.reduce(&[1], &t).unwrap();
+        let min = min.as_slice::<f32>().unwrap();
+        assert!(min[0].is_nan());
+        assert_eq!(min[1], 4.);
+    }
+
+    #[test]
+    fn argmax_argmin_first_nan() {
+        let t = tensor2(&[[1f32, std::f32::NAN, 3., std::f32::NAN], [4., 6., 6., 5.]]);
+        assert_eq!(Reducer::ArgMax(false).reduce(&[1], &t).unwrap(), tensor2(&[[1i64], [1]]));
+        assert_eq!(Reducer::ArgMax(true).reduce(&[1], &t).unwrap(), tensor2(&[[1i64], [2]]));
+        assert_eq!(Reducer::ArgMin(false).reduce(&[1], &t).unwrap(), tensor2(&[[1i64], [0]]));
+    }
+}
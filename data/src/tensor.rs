@@ -15,6 +15,7 @@ use std::ops::Range;
 use std::sync::Arc;
 
 pub mod litteral;
+pub mod quant;
 pub mod view;
 
 /// Tensor is a concrete tensor in tract.
@@ -29,6 +30,29 @@ pub struct Tensor {
 unsafe impl Send for Tensor {}
 unsafe impl Sync for Tensor {}
 
+/// Tolerances for `Tensor::approx_eq`.
+///
+/// Values `a` and `b` are equal if `|a - b| <= atol + rtol * |b|`. NaN are
+/// equal to each other only if `equal_nan` is set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ApproxEqOptions {
+    pub atol: f32,
+    pub rtol: f32,
+    pub equal_nan: bool,
+}
+
+impl Default for ApproxEqOptions {
+    fn default() -> ApproxEqOptions {
+        ApproxEqOptions { atol: 5e-4, rtol: 1e-4, equal_nan: true }
+    }
+}
+
+fn values_close(a: f32, b: f32, atol: f32, rtol: f32, equal_nan: bool) -> bool {
+    (equal_nan && a.is_nan() && b.is_nan())
+        || (a.is_infinite() && b.is_infinite() && a.signum() == b.signum())
+        || (a - b).abs() <= atol + rtol * b.abs()
+}
+
 impl Hash for Tensor {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         use DatumType::*;
@@ -189,6 +213,55 @@ impl Tensor {
         Ok(tensor)
     }
 
+    /// Concatenate tensors along `axis`.
+    ///
+    /// All tensors must share the same datum type, and the same shape on every
+    /// axis but `axis`.
+    pub fn concat(tensors: &[&Tensor], axis: usize) -> anyhow::Result<Tensor> {
+        if tensors.is_empty() {
+            anyhow::bail!("Can not concat an empty list of tensors")
+        }
+        let first = tensors[0];
+        if axis >= first.rank() {
+            anyhow::bail!("Can not concat on axis {} tensors of rank {}", axis, first.rank())
+        }
+        for t in &tensors[1..] {
+            if t.datum_type() != first.datum_type() {
+                anyhow::bail!(
+                    "Can not concat tensors of different datum types ({:?} and {:?})",
+                    first.datum_type(),
+                    t.datum_type()
+                )
+            }
+            if t.rank() != first.rank()
+                || izip!(0.., t.shape(), first.shape()).any(|(ix, a, b)| ix != axis && a != b)
+            {
+                anyhow::bail!(
+                    "Can not concat on axis {} tensors of incompatible shapes {:?} and {:?}",
+                    axis,
+                    first.shape(),
+                    t.shape()
+                )
+            }
+        }
+        Tensor::stack_tensors(axis, tensors)
+    }
+
+    /// Split the tensor along `axis` into `chunks` tensors of equal size.
+    ///
+    /// The length of `axis` must be divisible by `chunks`.
+    pub fn split(&self, axis: usize, chunks: usize) -> anyhow::Result<Vec<Tensor>> {
+        if axis >= self.rank() {
+            anyhow::bail!("Can not split on axis {} a tensor of rank {}", axis, self.rank())
+        }
+        let len = self.shape()[axis];
+        if chunks == 0 || len % chunks != 0 {
+            anyhow::bail!("Can not split axis {} of length {} in {} chunks", axis, len, chunks)
+        }
+        let size = len / chunks;
+        (0..chunks).map(|ix| self.slice(axis, ix * size, (ix + 1) * size)).collect()
+    }
+
     pub unsafe fn clear<T: Datum + num_traits::Zero>(&mut self) {
         self.as_slice_mut_unchecked::<T>().iter_mut().for_each(|item| *item = T::zero());
     }
@@ -213,6 +286,30 @@ impl Tensor {
         dispatch_numbers!(Self::zero(dt)(shape))
     }
 
+    pub fn one<T: Datum + num_traits::One>(shape: &[usize]) -> anyhow::Result<Tensor> {
+        unsafe {
+            let mut t = Tensor::uninitialized::<T>(shape)?;
+            t.as_slice_mut_unchecked::<T>().iter_mut().for_each(|item| *item = T::one());
+            Ok(t)
+        }
+    }
+
+    pub fn one_dt(dt: DatumType, shape: &[usize]) -> anyhow::Result<Tensor> {
+        dispatch_numbers!(Self::one(dt)(shape))
+    }
+
+    /// Create a tensor filled with `value`, cast to `dt`.
+    ///
+    /// For integer types, `value` is rounded to the nearest integer (halves
+    /// away from zero) then saturated to the range of `dt`.
+    pub fn full_dt(dt: DatumType, shape: &[usize], value: f64) -> anyhow::Result<Tensor> {
+        if !dt.is_float() && !dt.is_integer() {
+            anyhow::bail!("{:?} is not a number", dt)
+        }
+        let value = if dt.is_integer() { value.round() } else { value };
+        litteral::tensor0(value).cast_to_dt(dt)?.broadcast_scalar_to_shape(shape)
+    }
+
     pub fn zero_aligned_dt(
         dt: DatumType,
         shape: &[usize],
@@ -266,6 +363,56 @@ impl Tensor {
         Ok(tensor)
     }
 
+    /// Create a tensor from raw big-endian data.
+    ///
+    /// Multi-byte elements are byte-swapped to the native order.
+    pub unsafe fn from_raw_be<T: Datum>(shape: &[usize], content: &[u8]) -> anyhow::Result<Tensor> {
+        Tensor::from_raw_be_dt(T::datum_type(), shape, content)
+    }
+
+    /// Create a tensor from raw big-endian data.
+    ///
+    /// Multi-byte elements are byte-swapped to the native order.
+    pub unsafe fn from_raw_be_dt(
+        dt: DatumType,
+        shape: &[usize],
+        content: &[u8],
+    ) -> anyhow::Result<Tensor> {
+        if !dt.is_copy() {
+            anyhow::bail!("Can not load {:?} from raw data", dt)
+        }
+        let len = shape.iter().product::<usize>() * dt.size_of();
+        if content.len() != len {
+            anyhow::bail!("Expected {} bytes for {:?} {:?}, got {}", len, dt, shape, content.len())
+        }
+        let mut tensor = Tensor::from_raw_dt(dt, shape, content)?;
+        if cfg!(target_endian = "little") {
+            tensor.swap_bytes();
+        }
+        Ok(tensor)
+    }
+
+    /// Dump the tensor data as raw big-endian bytes.
+    pub fn to_raw_be(&self) -> anyhow::Result<Vec<u8>> {
+        if !self.datum_type().is_copy() {
+            anyhow::bail!("Can not dump {:?} as raw data", self.datum_type())
+        }
+        let mut tensor = self.clone();
+        unsafe {
+            if cfg!(target_endian = "little") {
+                tensor.swap_bytes();
+            }
+            Ok(tensor.as_bytes().to_vec())
+        }
+    }
+
+    unsafe fn swap_bytes(&mut self) {
+        let size = self.datum_type().size_of();
+        if size > 1 {
+            self.as_bytes_mut().chunks_exact_mut(size).for_each(|item| item.reverse());
+        }
+    }
+
     pub unsafe fn from_slice_align<T: Datum>(
         content: &[T],
         align: usize,
@@ -323,7 +470,13 @@ impl Tensor {
         Ok(())
     }
 
+    /// Reorder the axes: axis `i` of the result is axis `axes[i]` of `self`.
+    ///
+    /// `axes` must be a permutation of `0..rank`.
     pub fn permute_axes(self, axes: &[usize]) -> anyhow::Result<Tensor> {
+        if axes.len() != self.rank() || !(0..self.rank()).all(|ax| axes.contains(&ax)) {
+            anyhow::bail!("Invalid permutation {:?} for a tensor of rank {}", axes, self.rank());
+        }
         unsafe {
             #[inline]
             unsafe fn permute<T: Datum>(axes: &[usize], input: Tensor) -> Tensor {
@@ -386,6 +539,20 @@ impl Tensor {
         }
     }
 
+    /// Materialize the tensor broadcast to `shape`, following numpy rules: missing leading
+    /// axes and axes of size 1 are repeated to match `shape`.
+    pub fn broadcast_to_shape(&self, shape: &[usize]) -> anyhow::Result<Tensor> {
+        fn broadcast_t<T: Datum>(t: &Tensor, shape: &[usize]) -> anyhow::Result<Tensor> {
+            let view = t.to_array_view::<T>()?;
+            if let Some(view) = view.broadcast(shape) {
+                Ok(view.to_owned().into_tensor())
+            } else {
+                anyhow::bail!("Can not broadcast tensor of shape {:?} to {:?}", t.shape(), shape)
+            }
+        }
+        dispatch_datum!(broadcast_t(self.datum_type())(self, shape))
+    }
+
     fn clip_range_bounds(
         &self,
         axis: usize,
@@ -545,29 +712,41 @@ impl Tensor {
         }
     }
 
+    /// Dump the tensor, printing at most `max` elements.
+    ///
+    /// Bigger tensors are shown as the head and tail of their data around a note
+    /// of how many elements were elided.
+    pub fn dump_limited(&self, max: usize) -> anyhow::Result<String> {
+        use itertools::Itertools;
+        if self.len() <= max {
+            return self.dump(true);
+        }
+        unsafe fn dump_t<D: Datum>(tensor: &Tensor, range: Range<usize>) -> String {
+            tensor.as_slice_unchecked::<D>()[range].iter().join(", ")
+        }
+        let head = (max + 1) / 2;
+        let tail = self.len() - (max - head);
+        unsafe {
+            let head = dispatch_datum!(dump_t(self.datum_type())(self, 0..head));
+            let tail = dispatch_datum!(dump_t(self.datum_type())(self, tail..self.len()));
+            Ok(format!(
+                "{},{:?} {}, ...({} elided)..., {}",
+                self.shape.iter().join("x"),
+                self.dt,
+                head,
+                self.len() - max,
+                tail
+            ))
+        }
+    }
+
     /// Compare two tensors, allowing for rounding errors.
     pub fn close_enough(&self, other: &Self, approx: bool) -> anyhow::Result<()> {
         if self.shape() != other.shape() {
             anyhow::bail!("Shape mismatch {:?} != {:?}", self.shape(), other.shape())
         }
         if approx {
-            let atol = 5e-4;
-            let rtol = 1e-4;
-            let ma = self.cast_to::<f32>()?;
-            let ma = ma.to_array_view::<f32>()?;
-            let mb = other.cast_to::<f32>()?;
-            let mb = mb.to_array_view::<f32>()?;
-            ndarray::indices_of(&ma).into_iter().try_for_each(|indices| {
-                let a = ma[&indices];
-                let b = mb[&indices];
-                if !((a.is_nan() && b.is_nan())
-                    || (a.is_infinite() && b.is_infinite() && a.signum() == b.signum())
-                    || (a - b).abs() <= atol + rtol * b.abs())
-                {
-                    anyhow::bail!("Mismatch at {:?} {} != {}", indices.slice(), a, b)
-                }
-                Ok(())
-            })
+            self.close_enough_with(other, 5e-4, 1e-4)
         } else {
             if self.eq(other) {
                 Ok(())
@@ -577,6 +756,99 @@ impl Tensor {
         }
     }
 
+    /// Compare two tensors, allowing for an absolute and relative tolerance.
+    ///
+    /// Values `a` and `b` match if `|a - b| <= atol + rtol * |b|`.
+    pub fn close_enough_with(&self, other: &Self, atol: f32, rtol: f32) -> anyhow::Result<()> {
+        if self.shape() != other.shape() {
+            anyhow::bail!("Shape mismatch {:?} != {:?}", self.shape(), other.shape())
+        }
+        if self.datum_type() == other.datum_type()
+            && (self.datum_type().is_integer() || self.datum_type().is_float())
+        {
+            dispatch_numbers!(Self::close_enough_t(self.datum_type())(
+                self, other, atol, rtol, true
+            ))
+        } else {
+            let ma = self.cast_to::<f32>()?;
+            let mb = other.cast_to::<f32>()?;
+            Self::close_enough_t::<f32>(&ma, &mb, atol, rtol, true)
+        }
+    }
+
+    /// Boolean approximate equality, for assertions.
+    ///
+    /// Tensors with different datum types or shapes are never equal. Non
+    /// numeric tensors must be strictly equal.
+    pub fn approx_eq(&self, other: &Self, options: &ApproxEqOptions) -> bool {
+        self.approx_eq_dt(other, options).unwrap_or(false)
+    }
+
+    fn approx_eq_dt(&self, other: &Self, options: &ApproxEqOptions) -> anyhow::Result<bool> {
+        if self.datum_type() != other.datum_type() || self.shape() != other.shape() {
+            return Ok(false);
+        }
+        if !self.datum_type().is_integer() && !self.datum_type().is_float() {
+            return Ok(self == other);
+        }
+        let ApproxEqOptions { atol, rtol, equal_nan } = *options;
+        Ok(dispatch_numbers!(Self::close_enough_t(self.datum_type())(
+            self, other, atol, rtol, equal_nan
+        ))
+        .is_ok())
+    }
+
+    fn close_enough_t<T: Datum + num_traits::AsPrimitive<f32>>(
+        &self,
+        other: &Self,
+        atol: f32,
+        rtol: f32,
+        equal_nan: bool,
+    ) -> anyhow::Result<()> {
+        let ma = self.to_array_view::<T>()?;
+        let mb = other.to_array_view::<T>()?;
+        ndarray::indices_of(&ma).into_iter().try_for_each(|indices| {
+            let a: f32 = ma[&indices].as_();
+            let b: f32 = mb[&indices].as_();
+            if !values_close(a, b, atol, rtol, equal_nan) {
+                anyhow::bail!("Mismatch at {:?} {} != {}", indices.slice(), a, b)
+            }
+            Ok(())
+        })
+    }
+
+    /// Compute a u8 tensor of the same shape, holding 1 where values are out
+    /// of tolerance and 0 elsewhere.
+    pub fn diff_mask(&self, other: &Self, options: &ApproxEqOptions) -> anyhow::Result<Tensor> {
+        if self.datum_type() != other.datum_type() || self.shape() != other.shape() {
+            anyhow::bail!(
+                "Can not diff {:?} {:?} with {:?} {:?}",
+                self.datum_type(),
+                self.shape(),
+                other.datum_type(),
+                other.shape()
+            )
+        }
+        if !self.datum_type().is_integer() && !self.datum_type().is_float() {
+            anyhow::bail!("Can not diff non numeric {:?} tensors", self.datum_type())
+        }
+        dispatch_numbers!(Self::diff_mask_t(self.datum_type())(self, other, options))
+    }
+
+    fn diff_mask_t<T: Datum + num_traits::AsPrimitive<f32>>(
+        &self,
+        other: &Self,
+        options: &ApproxEqOptions,
+    ) -> anyhow::Result<Tensor> {
+        let ApproxEqOptions { atol, rtol, equal_nan } = *options;
+        let mut mask = self.to_array_view::<T>()?.mapv(|_| 0u8);
+        ndarray::Zip::from(&mut mask)
+            .and(&self.to_array_view::<T>()?)
+            .and(&other.to_array_view::<T>()?)
+            .apply(|m, a, b| *m = !values_close(a.as_(), b.as_(), atol, rtol, equal_nan) as u8);
+        Ok(mask.into_tensor())
+    }
+
     /// Transform the tensor into a `ndarray::Array`.
     pub fn into_array<D: Datum>(self) -> anyhow::Result<ArrayD<D>> {
         Ok(self.to_array_view::<D>()?.to_owned())
@@ -949,6 +1221,232 @@ impl Tensor {
         dispatch_datum!(slice_t(self.datum_type())(&self, axis, start, end))
     }
 
+    /// Pad the tensor with a constant.
+    ///
+    /// `pads` gives the (before, after) padding for each axis. `value` is cast to the
+    /// tensor datum type, with the same rules as `cast_to`.
+    pub fn pad(&self, pads: &[(usize, usize)], value: f32) -> anyhow::Result<Tensor> {
+        if pads.len() != self.rank() {
+            anyhow::bail!("Expected {} pads for tensor {:?}, got {:?}", self.rank(), self, pads)
+        }
+        let shape: TVec<usize> =
+            self.shape().iter().zip(pads.iter()).map(|(d, (b, a))| d + b + a).collect();
+        let mut result = litteral::tensor0(value)
+            .cast_to_dt(self.datum_type())?
+            .broadcast_scalar_to_shape(&shape)?;
+        unsafe fn assign_t<T: Datum>(dst: &mut Tensor, src: &Tensor, pads: &[(usize, usize)]) {
+            let mut view = dst.to_array_view_mut_unchecked::<T>();
+            for (axis, (&(before, _), &dim)) in pads.iter().zip(src.shape().iter()).enumerate() {
+                view.slice_axis_inplace(Axis(axis), (before..before + dim).into());
+            }
+            view.assign(&src.to_array_view_unchecked::<T>());
+        }
+        unsafe { dispatch_datum!(assign_t(self.datum_type())(&mut result, self, pads)) }
+        Ok(result)
+    }
+
+    /// Clamp every value to `[min, max]`, keeping the tensor datum type.
+    ///
+    /// Bounds are cast to the tensor datum type. For integer types, `min` is
+    /// rounded up and `max` down, so clipped values stay in the real range.
+    /// NaN values are left untouched.
+    pub fn clip(&self, min: f32, max: f32) -> anyhow::Result<Tensor> {
+        if min.is_nan() || max.is_nan() || min > max {
+            anyhow::bail!("Invalid clip range [{}, {}]", min, max)
+        }
+        let (min, max) =
+            if self.datum_type().is_float() { (min, max) } else { (min.ceil(), max.floor()) };
+        if min > max {
+            anyhow::bail!(
+                "Empty clip range [{}, {}] once rounded for {:?}",
+                min,
+                max,
+                self.datum_type()
+            )
+        }
+        let min = litteral::tensor0(min).cast_to_dt(self.datum_type())?.into_owned();
+        let max = litteral::tensor0(max).cast_to_dt(self.datum_type())?.into_owned();
+        fn clip_t<T: Datum + PartialOrd>(
+            t: &Tensor,
+            min: &Tensor,
+            max: &Tensor,
+        ) -> anyhow::Result<Tensor> {
+            let min = min.to_scalar::<T>()?;
+            let max = max.to_scalar::<T>()?;
+            Ok(t.to_array_view::<T>()?
+                .mapv(|v| {
+                    if v < *min {
+                        min.clone()
+                    } else if v > *max {
+                        max.clone()
+                    } else {
+                        v
+                    }
+                })
+                .into_tensor())
+        }
+        dispatch_numbers!(clip_t(self.datum_type())(self, &min, &max))
+    }
+
+    /// Pick values from `self` where `mask` is true, and from `other` elsewhere.
+    ///
+    /// `self` and `other` must have the same datum type and shape. `mask` is
+    /// cast to bool (so any non-zero number is true) and broadcast to that
+    /// shape.
+    pub fn select(&self, mask: &Tensor, other: &Tensor) -> anyhow::Result<Tensor> {
+        if self.datum_type() != other.datum_type() || self.shape() != other.shape() {
+            anyhow::bail!("Can not select between {:?} and {:?}", self, other)
+        }
+        let mask = mask.cast_to::<bool>()?.broadcast_to_shape(self.shape())?;
+        fn select_t<T: Datum>(
+            t: &Tensor,
+            mask: &Tensor,
+            other: &Tensor,
+        ) -> anyhow::Result<Tensor> {
+            let mut result = other.to_array_view::<T>()?.to_owned();
+            ndarray::Zip::from(&mut result)
+                .and(&t.to_array_view::<T>()?)
+                .and(&mask.to_array_view::<bool>()?)
+                .apply(|r, t, &m| {
+                    if m {
+                        *r = t.clone()
+                    }
+                });
+            Ok(result.into_tensor())
+        }
+        dispatch_datum!(select_t(self.datum_type())(self, &mask, other))
+    }
+
+    /// Iterate over coordinates and values (cast to f64), in row-major order.
+    pub fn iter_indexed(&self) -> anyhow::Result<impl Iterator<Item = (Vec<usize>, f64)>> {
+        let values = self.cast_to::<f64>()?.as_slice::<f64>()?.to_vec();
+        Ok(ndarray::indices(self.shape())
+            .into_iter()
+            .map(|coords| coords.slice().to_vec())
+            .zip(values))
+    }
+
+    /// Values cast to f32, flattened in row-major order.
+    pub fn to_f32_vec(&self) -> anyhow::Result<Vec<f32>> {
+        if self.len() == 0 {
+            return Ok(vec![]);
+        }
+        Ok(self.cast_to::<f32>()?.as_slice::<f32>()?.to_vec())
+    }
+
+    /// Count values (cast to f32) into `bins` equal-width bins.
+    ///
+    /// Returns the `bins + 1` bin edges and the counts. Without a `range`, the
+    /// data min and max are used. The last bin includes its upper edge, values
+    /// outside the range and NaN are not counted. As in numpy, a degenerate
+    /// range is widened by 0.5 on each side.
+    pub fn histogram(
+        &self,
+        bins: usize,
+        range: Option<(f32, f32)>,
+    ) -> anyhow::Result<(Vec<f32>, Vec<usize>)> {
+        if bins == 0 {
+            anyhow::bail!("Histogram needs at least one bin")
+        }
+        if self.len() == 0 {
+            anyhow::bail!("Can not compute histogram of empty tensor {:?}", self)
+        }
+        let values = self.cast_to::<f32>()?;
+        let values = values.as_slice::<f32>()?;
+        let (mut lo, mut hi) = if let Some(range) = range {
+            range
+        } else {
+            values
+                .iter()
+                .filter(|v| !v.is_nan())
+                .fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |acc, &v| {
+                    (acc.0.min(v), acc.1.max(v))
+                })
+        };
+        if !lo.is_finite() || !hi.is_finite() || lo > hi {
+            anyhow::bail!("Invalid histogram range [{}, {}]", lo, hi)
+        }
+        if lo == hi {
+            lo -= 0.5;
+            hi += 0.5;
+        }
+        let width = (hi - lo) / bins as f32;
+        let edges = (0..=bins).map(|ix| lo + width * ix as f32).collect::<Vec<f32>>();
+        let mut counts = vec![0; bins];
+        for &v in values {
+            if v >= lo && v <= hi {
+                counts[(((v - lo) / width) as usize).min(bins - 1)] += 1;
+            }
+        }
+        Ok((edges, counts))
+    }
+
+    /// Euclidean norm of the values, cast to f64. Zero for an empty tensor.
+    pub fn l2_norm(&self) -> anyhow::Result<f64> {
+        if self.len() == 0 {
+            return Ok(0.);
+        }
+        let values = self.cast_to::<f64>()?;
+        Ok(values.as_slice::<f64>()?.iter().map(|x| x * x).sum::<f64>().sqrt())
+    }
+
+    /// Mean of the absolute values, cast to f64. Zero for an empty tensor.
+    pub fn mean_abs(&self) -> anyhow::Result<f64> {
+        if self.len() == 0 {
+            return Ok(0.);
+        }
+        let values = self.cast_to::<f64>()?;
+        Ok(values.as_slice::<f64>()?.iter().map(|x| x.abs()).sum::<f64>() / self.len() as f64)
+    }
+
+    /// Greatest absolute value, cast to f64. Zero for an empty tensor, NaN if
+    /// any value is NaN.
+    pub fn max_abs(&self) -> anyhow::Result<f64> {
+        if self.len() == 0 {
+            return Ok(0.);
+        }
+        let values = self.cast_to::<f64>()?;
+        Ok(values.as_slice::<f64>()?.iter().fold(0f64, |acc, x| {
+            if acc.is_nan() || x.is_nan() {
+                std::f64::NAN
+            } else {
+                acc.max(x.abs())
+            }
+        }))
+    }
+
+    /// Index of the greatest value along `axis`, as an I64 tensor with `axis` removed.
+    ///
+    /// Ties resolve to the lowest index. NaN wins over any other value: a
+    /// lane containing NaN yields the index of its first NaN.
+    pub fn argmax(&self, axis: usize) -> anyhow::Result<Tensor> {
+        if axis >= self.rank() {
+            anyhow::bail!("Can not compute argmax on axis {} of tensor {:?}", axis, self);
+        }
+        if self.shape()[axis] == 0 {
+            anyhow::bail!("Can not compute argmax on empty axis {} of tensor {:?}", axis, self);
+        }
+        fn argmax_t<T: Datum + PartialOrd>(t: &Tensor, axis: usize) -> anyhow::Result<Tensor> {
+            Ok(t.to_array_view::<T>()?
+                .map_axis(Axis(axis), |lane| {
+                    let mut best = 0;
+                    for (ix, v) in lane.iter().enumerate() {
+                        #[allow(clippy::eq_op)]
+                        let nan = v != v;
+                        if nan {
+                            return ix as i64;
+                        }
+                        if *v > lane[best] {
+                            best = ix;
+                        }
+                    }
+                    best as i64
+                })
+                .into_tensor())
+        }
+        dispatch_numbers!(argmax_t(self.datum_type())(self, axis))
+    }
+
     pub fn view(&self) -> view::TensorView {
         unsafe { view::TensorView::at_prefix_unchecked(self, &[]) }
     }
@@ -1094,3 +1592,262 @@ impl IntoArcTensor for Arc<Tensor> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::internal::*;
+
+    #[test]
+    fn argmax_per_row() {
+        let t = tensor2(&[[1f32, 3., 2., 3.], [4., 0., 1., 2.]]);
+        assert_eq!(t.argmax(1).unwrap(), tensor1(&[1i64, 0]));
+    }
+
+    #[test]
+    fn argmax_first_nan() {
+        let t = tensor2(&[[1f32, 3., std::f32::NAN, std::f32::NAN], [4., 0., 1., 2.]]);
+        assert_eq!(t.argmax(1).unwrap(), tensor1(&[2i64, 0]));
+    }
+
+    #[test]
+    fn argmax_invalid_axis() {
+        let t = tensor2(&[[1f32, 3., 2., 3.], [4., 0., 1., 2.]]);
+        assert!(t.argmax(2).is_err());
+    }
+
+    #[test]
+    fn from_raw_be_i32() {
+        let bytes = [0u8, 0, 0, 1, 0xff, 0xff, 0xff, 0xfe];
+        let t = unsafe { Tensor::from_raw_be::<i32>(&[2], &bytes).unwrap() };
+        assert_eq!(t, tensor1(&[1i32, -2]));
+        assert_eq!(t.to_raw_be().unwrap(), bytes);
+    }
+
+    #[test]
+    fn pad_2x2() {
+        let t = tensor2(&[[1f32, 2.], [3., 4.]]);
+        let padded = t.pad(&[(1, 1), (1, 1)], 9.).unwrap();
+        assert_eq!(
+            padded,
+            tensor2(&[[9f32, 9., 9., 9.], [9., 1., 2., 9.], [9., 3., 4., 9.], [9., 9., 9., 9.]])
+        );
+        assert!(t.pad(&[(1, 1)], 0.).is_err());
+    }
+
+    #[test]
+    fn concat_axis_0() {
+        let a = tensor2(&[[1f32, 2., 3.]]);
+        let b = tensor2(&[[4f32, 5., 6.]]);
+        let c = Tensor::concat(&[&a, &b], 0).unwrap();
+        assert_eq!(c, tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]));
+    }
+
+    #[test]
+    fn concat_mismatched_shapes() {
+        let a = tensor2(&[[1f32, 2., 3.]]);
+        let b = tensor2(&[[4f32, 5.]]);
+        assert!(Tensor::concat(&[&a, &b], 0).is_err());
+    }
+
+    #[test]
+    fn permute_axes_2_3_4() {
+        let t = Tensor::from(ndarray::Array3::from_shape_fn((2, 3, 4), |(a, b, c)| {
+            (a * 100 + b * 10 + c) as i32
+        }));
+        let p = t.permute_axes(&[2, 0, 1]).unwrap();
+        assert_eq!(p.shape(), &[4, 2, 3]);
+        assert_eq!(p.to_array_view::<i32>().unwrap()[[3, 1, 2]], 123);
+    }
+
+    #[test]
+    fn permute_axes_invalid() {
+        let t = Tensor::zero::<f32>(&[2, 3, 4]).unwrap();
+        assert!(t.clone().permute_axes(&[0, 1]).is_err());
+        assert!(t.clone().permute_axes(&[0, 1, 1]).is_err());
+        assert!(t.permute_axes(&[0, 1, 3]).is_err());
+    }
+
+    #[test]
+    fn split_axis_0() {
+        let t = Tensor::from(ndarray::Array2::from_shape_fn((6, 3), |(a, b)| (a * 3 + b) as f32));
+        let chunks = t.split(0, 3).unwrap();
+        assert_eq!(chunks.len(), 3);
+        for (ix, chunk) in chunks.iter().enumerate() {
+            assert_eq!(chunk.shape(), &[2, 3]);
+            assert_eq!(chunk, &t.slice(0, 2 * ix, 2 * ix + 2).unwrap());
+        }
+        assert_eq!(chunks[2].as_slice::<f32>().unwrap(), &[12., 13., 14., 15., 16., 17.]);
+    }
+
+    #[test]
+    fn split_not_divisible() {
+        let t = Tensor::zero::<f32>(&[6, 3]).unwrap();
+        assert!(t.split(1, 2).is_err());
+    }
+
+    #[test]
+    fn broadcast_to_shape() {
+        let t = tensor1(&[1i32, 2, 3]);
+        assert_eq!(t.broadcast_to_shape(&[2, 3]).unwrap(), tensor2(&[[1i32, 2, 3], [1, 2, 3]]));
+        let b = t.broadcast_to_shape(&[2, 4, 3]).unwrap();
+        assert_eq!(b.shape(), &[2, 4, 3]);
+        assert_eq!(b.as_slice::<i32>().unwrap(), &*[1, 2, 3].repeat(8));
+        assert!(t.broadcast_to_shape(&[2, 4]).is_err());
+    }
+
+    #[test]
+    fn close_enough_same_dt() {
+        let a = tensor1(&[1f32, 2., 3.]);
+        assert!(a.close_enough(&tensor1(&[1f32, 2.0001, 3.]), true).is_ok());
+        assert_eq!(
+            a.close_enough(&tensor1(&[1f32, 2.5, 3.]), true).unwrap_err().to_string(),
+            "Mismatch at [1] 2 != 2.5"
+        );
+        let a = tensor1(&[1u8, 2, 3]);
+        assert!(a.close_enough(&tensor1(&[1u8, 2, 3]), true).is_ok());
+        assert_eq!(
+            a.close_enough(&tensor1(&[1u8, 2, 4]), true).unwrap_err().to_string(),
+            "Mismatch at [2] 3 != 4"
+        );
+    }
+
+    #[test]
+    fn close_enough_mixed_dt() {
+        let a = tensor1(&[1u8, 2, 3]);
+        assert!(a.close_enough(&tensor1(&[1f32, 2., 3.]), true).is_ok());
+        assert!(a.close_enough(&tensor1(&[1f32, 2., 3.5]), true).is_err());
+    }
+
+    #[test]
+    fn clip_f32() {
+        let t = tensor2(&[[-1f32, 0., 0.25], [0.5, 1., 3.]]);
+        let c = t.clip(0., 1.).unwrap();
+        assert_eq!(c, tensor2(&[[0f32, 0., 0.25], [0.5, 1., 1.]]));
+    }
+
+    #[test]
+    fn clip_i32_rounds_bounds_inward() {
+        let t = tensor1(&[-3i32, 0, 1, 2, 3]);
+        assert_eq!(t.clip(-0.5, 2.5).unwrap(), tensor1(&[0i32, 0, 1, 2, 2]));
+        assert!(t.clip(1., 0.).is_err());
+        assert!(t.clip(0.2, 0.8).is_err());
+        assert_eq!(t.clip(0.2, 1.8).unwrap(), tensor1(&[1i32, 1, 1, 1, 1]));
+        assert!(tensor1(&[0.5f32]).clip(0.2, 0.8).is_ok());
+    }
+
+    #[test]
+    fn close_enough_with_tolerance() {
+        let a = tensor1(&[1f32, 2., 3.]);
+        let b = tensor1(&[1f32, 2.05, 3.]);
+        assert!(a.close_enough(&b, true).is_err());
+        assert!(a.close_enough_with(&b, 0.1, 0.).is_ok());
+        assert!(a.close_enough_with(&b, 0., 0.01).is_err());
+        assert!(a.close_enough_with(&b, 0., 0.05).is_ok());
+    }
+
+    #[test]
+    fn iter_indexed_2x2() {
+        let t = tensor2(&[[1i32, 2], [3, 4]]);
+        let items = t.iter_indexed().unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            vec![(vec![0, 0], 1.), (vec![0, 1], 2.), (vec![1, 0], 3.), (vec![1, 1], 4.)]
+        );
+    }
+
+    #[test]
+    fn select_interleaved() {
+        let a = tensor1(&[1f32, 2., 3., 4.]);
+        let b = tensor1(&[10f32, 20., 30., 40.]);
+        let mask = tensor1(&[true, false, true, false]);
+        assert_eq!(a.select(&mask, &b).unwrap(), tensor1(&[1f32, 20., 3., 40.]));
+        let mask = tensor1(&[0u8, 1, 0, 1]);
+        assert_eq!(a.select(&mask, &b).unwrap(), tensor1(&[10f32, 2., 30., 4.]));
+    }
+
+    #[test]
+    fn select_broadcast_mask() {
+        let a = tensor2(&[[1i32, 2], [3, 4]]);
+        let b = tensor2(&[[0i32, 0], [0, 0]]);
+        let mask = tensor1(&[true, false]);
+        assert_eq!(a.select(&mask, &b).unwrap(), tensor2(&[[1i32, 0], [3, 0]]));
+        assert!(a.select(&tensor1(&[true, false, true]), &b).is_err());
+    }
+
+    #[test]
+    fn histogram_4_bins() {
+        let t = tensor1(&[0f32, 0.5, 1., 1.5, 2., 2.5, 3., 3.5, 4.]);
+        let (edges, counts) = t.histogram(4, None).unwrap();
+        assert_eq!(edges, vec![0., 1., 2., 3., 4.]);
+        assert_eq!(counts, vec![2, 2, 2, 3]);
+        assert_eq!(counts.iter().sum::<usize>(), t.len());
+        let (_, counts) = t.histogram(4, Some((1., 3.))).unwrap();
+        assert_eq!(counts, vec![1, 1, 1, 2]);
+        assert!(tensor1::<f32>(&[]).histogram(4, None).is_err());
+    }
+
+    #[test]
+    fn scalar_summaries() {
+        let t = tensor1(&[3i32, -4, 0, 1]);
+        assert_eq!(t.l2_norm().unwrap(), 26f64.sqrt());
+        assert_eq!(t.mean_abs().unwrap(), 2.);
+        assert_eq!(t.max_abs().unwrap(), 4.);
+        assert!(tensor1(&[1f32, std::f32::NAN, 2.]).max_abs().unwrap().is_nan());
+        let empty = tensor1::<f32>(&[]);
+        assert_eq!(empty.l2_norm().unwrap(), 0.);
+        assert_eq!(empty.mean_abs().unwrap(), 0.);
+        assert_eq!(empty.max_abs().unwrap(), 0.);
+    }
+
+    #[test]
+    fn constant_constructors() {
+        let z = Tensor::zero_dt(DatumType::F32, &[2, 3]).unwrap();
+        assert_eq!(z, tensor2(&[[0f32; 3]; 2]));
+        let o = Tensor::one_dt(DatumType::U8, &[2, 3]).unwrap();
+        assert_eq!(o, tensor2(&[[1u8; 3]; 2]));
+        let f = Tensor::full_dt(DatumType::F32, &[2], 0.25).unwrap();
+        assert_eq!(f, tensor1(&[0.25f32, 0.25]));
+        assert_eq!(Tensor::full_dt(DatumType::U8, &[2], 2.5).unwrap(), tensor1(&[3u8, 3]));
+        assert_eq!(Tensor::full_dt(DatumType::U8, &[1], 300.).unwrap(), tensor1(&[255u8]));
+        assert_eq!(Tensor::full_dt(DatumType::U8, &[1], -1.).unwrap(), tensor1(&[0u8]));
+        assert!(Tensor::one_dt(DatumType::String, &[2]).is_err());
+        assert!(Tensor::full_dt(DatumType::Bool, &[2], 1.).is_err());
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = tensor1(&[1f32, std::f32::NAN, 3.]);
+        let b = tensor1(&[1.0001f32, std::f32::NAN, 3.]);
+        assert!(a.approx_eq(&b, &ApproxEqOptions::default()));
+        assert!(a != b);
+        let strict_nan = ApproxEqOptions { equal_nan: false, ..ApproxEqOptions::default() };
+        assert!(!a.approx_eq(&b, &strict_nan));
+        let tight = ApproxEqOptions { atol: 0., rtol: 0., equal_nan: true };
+        assert!(!a.approx_eq(&b, &tight));
+        assert!(!tensor1(&[1f32, 2.]).approx_eq(&tensor1(&[1f64, 2.]), &tight));
+    }
+
+    #[test]
+    fn diff_mask() {
+        let a = tensor2(&[[1f32, 2., 3.], [4., 5., 6.]]);
+        let b = tensor2(&[[1.0001f32, 2.5, 3.], [4., 5., 7.]]);
+        let mask = a.diff_mask(&b, &ApproxEqOptions::default()).unwrap();
+        assert_eq!(mask, tensor2(&[[0u8, 1, 0], [0, 0, 1]]));
+        assert!(a.diff_mask(&a.cast_to::<f64>().unwrap(), &ApproxEqOptions::default()).is_err());
+        assert!(a.diff_mask(&tensor1(&[1f32]), &ApproxEqOptions::default()).is_err());
+    }
+
+    #[test]
+    fn to_f32_vec_u8() {
+        let t = tensor2(&[[1u8, 2], [3, 255]]);
+        assert_eq!(t.to_f32_vec().unwrap(), vec![1f32, 2., 3., 255.]);
+    }
+
+    #[test]
+    fn dump_limited() {
+        let small = tensor1(&[1i32, 2, 3]);
+        assert_eq!(small.dump_limited(3).unwrap(), "3,I32 1, 2, 3");
+        let big = tensor1(&(0..1000).collect::<Vec<i32>>());
+        assert_eq!(big.dump_limited(5).unwrap(), "1000,I32 0, 1, 2, ...(995 elided)..., 998, 999");
+    }
+}
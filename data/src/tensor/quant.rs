@@ -0,0 +1,107 @@
+use crate::datum::{Datum, DatumType};
+use crate::tensor::Tensor;
+use anyhow::*;
+
+/// A quantized tensor along with its affine quantization parameters.
+///
+/// `real = scale * (quantized - zero_point)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedTensor {
+    pub data: Tensor,
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+impl QuantizedTensor {
+    pub fn new(data: Tensor, scale: f32, zero_point: i32) -> Result<QuantizedTensor> {
+        if !data.datum_type().is_integer() {
+            bail!("Quantized data must be integer, got {:?}", data.datum_type())
+        }
+        Ok(QuantizedTensor { data, scale, zero_point })
+    }
+
+    /// Quantize a float tensor to `dt`, rounding to nearest and saturating to
+    /// the range of `dt`.
+    pub fn quantize(
+        real: &Tensor,
+        dt: DatumType,
+        scale: f32,
+        zero_point: i32,
+    ) -> Result<QuantizedTensor> {
+        fn q<T: Datum + num_traits::Bounded + num_traits::AsPrimitive<f32>>(
+            real: &Tensor,
+            scale: f32,
+            zero_point: i32,
+        ) -> Result<Tensor>
+        where
+            f32: num_traits::AsPrimitive<T>,
+        {
+            use num_traits::AsPrimitive;
+            let real = real.cast_to::<f32>()?;
+            let min: f32 = T::min_value().as_();
+            let max: f32 = T::max_value().as_();
+            Ok(real
+                .to_array_view::<f32>()?
+                .mapv(|x| ((x / scale).round() + zero_point as f32).max(min).min(max).as_())
+                .into())
+        }
+        let data = match dt {
+            DatumType::U8 => q::<u8>(real, scale, zero_point)?,
+            DatumType::I8 => q::<i8>(real, scale, zero_point)?,
+            DatumType::U16 => q::<u16>(real, scale, zero_point)?,
+            DatumType::I16 => q::<i16>(real, scale, zero_point)?,
+            DatumType::I32 => q::<i32>(real, scale, zero_point)?,
+            _ => bail!("Can not quantize to {:?}", dt),
+        };
+        Ok(QuantizedTensor { data, scale, zero_point })
+    }
+
+    /// Map the quantized values back to f32.
+    ///
+    /// The offset is computed in f64, exact for any zero point and for
+    /// integer data up to 2^53.
+    pub fn dequantize(&self) -> Result<Tensor> {
+        let data = self.data.cast_to::<f64>()?;
+        let (scale, zero_point) = (self.scale as f64, self.zero_point as f64);
+        Ok(data.to_array_view::<f64>()?.mapv(|x| (scale * (x - zero_point)) as f32).into())
+    }
+
+    /// Compare two quantized tensors on their dequantized values.
+    pub fn close_enough(&self, other: &Self, approx: bool) -> Result<()> {
+        self.dequantize()?.close_enough(&other.dequantize()?, approx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn one_lsb_is_close_after_dequant() {
+        let a = QuantizedTensor::new(tensor1(&[10u8, 20, 30]), 1e-4, 128).unwrap();
+        let b = QuantizedTensor::new(tensor1(&[10u8, 21, 30]), 1e-4, 128).unwrap();
+        assert!(a.data.close_enough(&b.data, true).is_err());
+        assert!(a.close_enough(&b, true).is_ok());
+    }
+
+    #[test]
+    fn quantize_round_trip() {
+        let real = tensor1(&[-1f32, 0., 0.5, 10.]);
+        let q = QuantizedTensor::quantize(&real, DatumType::U8, 0.5, 10).unwrap();
+        assert_eq!(q.data, tensor1(&[8u8, 10, 11, 30]));
+        assert_eq!(q.dequantize().unwrap(), tensor1(&[-1f32, 0., 0.5, 10.]));
+        let sat = QuantizedTensor::quantize(&tensor1(&[-100f32, 1000.]), DatumType::U8, 1., 0);
+        assert_eq!(sat.unwrap().data, tensor1(&[0u8, 255]));
+    }
+
+    #[test]
+    fn dequantize_wide_data() {
+        let q = QuantizedTensor::new(tensor1(&[4_000_000_000u32, 0]), 1., 0).unwrap();
+        assert_eq!(q.dequantize().unwrap(), tensor1(&[4e9f32, 0.]));
+        let q = QuantizedTensor::new(tensor1(&[std::i32::MAX, 0]), 0.5, std::i32::MIN).unwrap();
+        assert_eq!(q.dequantize().unwrap(), tensor1(&[2f32.powi(32) / 2. - 0.5, 2f32.powi(30)]));
+        let q = QuantizedTensor::new(tensor1(&[1i64 << 40]), 1., -1).unwrap();
+        assert_eq!(q.dequantize().unwrap(), tensor1(&[(1u64 << 40) as f32 + 1.]));
+    }
+}
@@ -45,16 +45,109 @@ pub fn imagenet_slim_labels() -> path::PathBuf {
     inception_v3_2016_08_28().join("imagenet_slim_labels.txt")
 }
 
+/// Axes order of an image batch tensor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChannelOrder {
+    NHWC,
+    NCHW,
+}
+
+/// Input preparation applied to decoded RGB pixels before they are fed to
+/// the network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preprocessing {
+    pub width: u32,
+    pub height: u32,
+    /// Pixel values are divided by `divisor`.
+    pub divisor: f32,
+    pub layout: ChannelOrder,
+}
+
+impl Default for Preprocessing {
+    fn default() -> Preprocessing {
+        Preprocessing { width: 299, height: 299, divisor: 255.0, layout: ChannelOrder::NHWC }
+    }
+}
+
+/// Resize and normalize already decoded pixels, laid out as HWC (channels
+/// last, RGB), to a 1xHxWx3 (NHWC) or 1x3xHxW (NCHW) f32 tensor.
+pub fn preprocess(rgb: tract_ndarray::ArrayD<u8>, prep: &Preprocessing) -> TractResult<Tensor> {
+    if rgb.ndim() != 3 || rgb.shape()[2] != 3 {
+        bail!("Expected HWC pixels with 3 channels, got shape {:?}", rgb.shape());
+    }
+    let (h, w) = (rgb.shape()[0] as u32, rgb.shape()[1] as u32);
+    let pixels = rgb.as_standard_layout().iter().cloned().collect::<Vec<u8>>();
+    let image = image::RgbImage::from_raw(w, h, pixels).context("Invalid pixel buffer")?;
+    let resized = image::imageops::resize(
+        &image,
+        prep.width,
+        prep.height,
+        ::image::imageops::FilterType::Triangle,
+    );
+    let (h, w) = (prep.height as usize, prep.width as usize);
+    let pixel = |y: usize, x: usize, c: usize| resized[(x as _, y as _)][c] as f32 / prep.divisor;
+    let image = match prep.layout {
+        ChannelOrder::NHWC => {
+            tract_ndarray::Array4::from_shape_fn((1, h, w, 3), |(_, y, x, c)| pixel(y, x, c))
+        }
+        ChannelOrder::NCHW => {
+            tract_ndarray::Array4::from_shape_fn((1, 3, h, w), |(_, c, y, x)| pixel(y, x, c))
+        }
+    };
+    Ok(image.into_dyn().into())
+}
+
+/// Decode an image file to HWC RGB pixels.
+pub fn decode_image<P: AsRef<path::Path>>(p: P) -> TractResult<tract_ndarray::ArrayD<u8>> {
+    let image = image::open(&p)?.to_rgb8();
+    let (w, h) = image.dimensions();
+    Ok(tract_ndarray::Array3::from_shape_vec((h as usize, w as usize, 3), image.into_raw())?
+        .into_dyn())
+}
+
 pub fn load_image<P: AsRef<path::Path>>(p: P) -> Tensor {
-    let image = image::open(&p).unwrap().to_rgb8();
-    let resized =
-        image::imageops::resize(&image, 299, 299, ::image::imageops::FilterType::Triangle);
-    let image = tract_ndarray::Array4::from_shape_fn((1, 299, 299, 3), |(_, y, x, c)| {
-        resized[(x as _, y as _)][c] as f32 / 255.0
-    })
-    .into_dyn()
-    .into();
-    image
+    preprocess(decode_image(p).unwrap(), &Preprocessing::default()).unwrap()
+}
+
+/// Write a channel of a rank 4 image batch tensor as a grayscale PNG, for
+/// visual debugging. Values are linearly mapped from their min..max range to
+/// 0..255, a constant channel is written black.
+pub fn channel_to_png<P: AsRef<path::Path>>(
+    tensor: &Tensor,
+    path: P,
+    batch: usize,
+    channel: usize,
+    layout: ChannelOrder,
+) -> TractResult<()> {
+    if tensor.rank() != 4 {
+        bail!("Expected a rank 4 tensor, got {:?}", tensor.shape())
+    }
+    let channel_axis = if layout == ChannelOrder::NHWC { 3 } else { 1 };
+    if batch >= tensor.shape()[0] || channel >= tensor.shape()[channel_axis] {
+        bail!(
+            "No batch {} and channel {} in tensor of shape {:?} ({:?})",
+            batch,
+            channel,
+            tensor.shape(),
+            layout
+        )
+    }
+    let values = tensor.cast_to::<f32>()?;
+    let values = values.to_array_view::<f32>()?;
+    let values = values.index_axis(tract_ndarray::Axis(0), batch);
+    let plane = values.index_axis(tract_ndarray::Axis(channel_axis - 1), channel);
+    let (min, max) =
+        plane.iter().fold((std::f32::INFINITY, std::f32::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+    let (h, w) = (plane.shape()[0], plane.shape()[1]);
+    let image = image::GrayImage::from_fn(w as u32, h as u32, |x, y| {
+        let v = plane[[y as usize, x as usize]];
+        let v = if max > min { ((v - min) / (max - min) * 255.0).round() as u8 } else { 0 };
+        image::Luma([v])
+    });
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(())
 }
 
 #[cfg(test)]
@@ -97,4 +190,65 @@ mod tests {
         let label = &labels[label_id];
         assert_eq!(label, "military uniform");
     }
+
+    #[test]
+    fn preprocess_decoded_pixels_like_load_image() {
+        let rgb = image::open(hopper()).unwrap().to_rgb8();
+        let (w, h) = rgb.dimensions();
+        let pixels =
+            tract_ndarray::Array3::from_shape_vec((h as usize, w as usize, 3), rgb.into_raw())
+                .unwrap()
+                .into_dyn();
+        let tensor = preprocess(pixels, &Preprocessing::default()).unwrap();
+        assert_eq!(tensor.shape(), &[1, 299, 299, 3]);
+        assert_eq!(tensor, load_image(hopper()));
+        // values from the loader before the split into decode and preprocess
+        let values = tensor.to_array_view::<f32>().unwrap();
+        for &(y, x, rgb) in
+            &[(0, 0, [22, 27, 83]), (100, 150, [229, 127, 94]), (298, 298, [12, 11, 17])]
+        {
+            for c in 0..3 {
+                assert_eq!(values[[0, y, x, c]], rgb[c] as f32 / 255.0);
+            }
+        }
+        let checksum = values.iter().map(|v| v.to_bits() as u64).sum::<u64>();
+        assert_eq!(checksum, 280222239467467);
+    }
+
+    #[test]
+    fn nchw_is_permuted_nhwc() {
+        let nhwc = load_image(hopper());
+        let prep = Preprocessing { layout: ChannelOrder::NCHW, ..Preprocessing::default() };
+        let nchw = preprocess(decode_image(hopper()).unwrap(), &prep).unwrap();
+        assert_eq!(nchw.shape(), &[1, 3, 299, 299]);
+        assert_eq!(nchw, nhwc.permute_axes(&[0, 3, 1, 2]).unwrap());
+    }
+
+    #[test]
+    fn dump_channel_as_png() {
+        // 1x2x3x2 NHWC, channel 1 holds 0, 10, ..., 50
+        let t = tract_ndarray::Array4::from_shape_fn((1, 2, 3, 2), |(_, y, x, c)| {
+            (c * 100 + 10 * (3 * y + x)) as f32
+        })
+        .into_tensor();
+        let path =
+            std::env::temp_dir().join(format!("tf-inceptionv3-channel-{}.png", std::process::id()));
+        channel_to_png(&t, &path, 0, 1, ChannelOrder::NHWC).unwrap();
+        let png = image::open(&path).unwrap().to_luma8();
+        assert_eq!(png.dimensions(), (3, 2));
+        assert_eq!(png[(0, 0)][0], 0);
+        assert_eq!(png[(1, 1)][0], 204);
+        assert_eq!(png[(2, 1)][0], 255);
+        assert!(channel_to_png(&t, &path, 0, 2, ChannelOrder::NHWC).is_err());
+        assert!(channel_to_png(&t, &path, 0, 2, ChannelOrder::NCHW).is_err());
+        assert!(channel_to_png(
+            &t.into_shape(&[2, 3, 2]).unwrap(),
+            &path,
+            0,
+            0,
+            ChannelOrder::NHWC
+        )
+        .is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
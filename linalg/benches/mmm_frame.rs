@@ -0,0 +1,111 @@
+use criterion::*;
+use tract_data::internal::*;
+use tract_linalg::mmm::MatrixStoreSpec;
+
+use DatumType::F32;
+
+// m x k x n, can be overriden with TRACT_MMM_BENCH_SHAPES="64x64x64,256x128x1024"
+const SHAPES: &[(usize, usize, usize)] =
+    &[(16, 16, 16), (64, 64, 64), (64, 288, 1024), (256, 256, 256), (512, 512, 512)];
+
+fn shapes() -> Vec<(usize, usize, usize)> {
+    if let Ok(spec) = std::env::var("TRACT_MMM_BENCH_SHAPES") {
+        spec.split(',')
+            .map(|s| {
+                let dims: Vec<usize> =
+                    s.trim().split('x').map(|d| d.parse().expect("invalid shape")).collect();
+                assert_eq!(dims.len(), 3, "shapes are expected as mxkxn");
+                (dims[0], dims[1], dims[2])
+            })
+            .collect()
+    } else {
+        SHAPES.to_vec()
+    }
+}
+
+// A is always packed: the frame can not read it any other way.
+#[derive(Copy, Clone, Debug)]
+enum BLayout {
+    Packed,
+    Strides,
+    OffsetsAndPtrs,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum CLayout {
+    View,
+    Strides,
+}
+
+unsafe fn run(
+    be: &mut Bencher,
+    (m, k, n): (usize, usize, usize),
+    b_layout: BLayout,
+    c_layout: CLayout,
+) {
+    let mm = tract_linalg::ops().mmm(F32, F32, F32, m, k, n).unwrap();
+    let a = Tensor::from(tract_ndarray::Array2::from_shape_fn((m, k), |(i, j)| (i + j) as f32));
+    let b = Tensor::from(tract_ndarray::Array2::from_shape_fn((k, n), |(i, j)| (i * j) as f32));
+    let mut pa =
+        Tensor::uninitialized_aligned::<f32>(&[mm.a_pack().len(m)], mm.a_pack().alignment())
+            .unwrap();
+    mm.a_pack().pack(&mut pa.view_mut(), &a.view(), 1, 0);
+    let (b, b_spec) = match b_layout {
+        BLayout::Packed => {
+            let mut pb = Tensor::uninitialized_aligned::<f32>(
+                &[mm.b_pack().len(n)],
+                mm.b_pack().alignment(),
+            )
+            .unwrap();
+            mm.b_pack().pack(&mut pb.view_mut(), &b.view(), 0, 1);
+            (pb, mm.b_packed())
+        }
+        BLayout::Strides => {
+            (b, MatrixStoreSpec::Strides { row_byte_stride: 4 * n as isize, col_byte_stride: 4 })
+        }
+        BLayout::OffsetsAndPtrs => {
+            let rows_offsets: Vec<isize> = (0..k).map(|i| (i * n) as isize).collect();
+            let cols_offsets: Vec<isize> = (0..n).map(|i| i as isize).collect();
+            (b, mm.b_from_data_and_offsets(&rows_offsets, &cols_offsets))
+        }
+    };
+    let (mut c, c_spec) = match c_layout {
+        CLayout::View => (Tensor::zero::<f32>(&[m, n]).unwrap(), mm.c_view()),
+        CLayout::Strides => {
+            (Tensor::zero::<f32>(&[n, m]).unwrap(), mm.c_from_data_and_strides(1, m as isize))
+        }
+    };
+    let a_spec = mm.a_packed();
+    let a_view = pa.view();
+    let a_store = a_spec.wrap(&a_view);
+    let b_view = b.view();
+    let b_store = b_spec.wrap(&b_view);
+    let c_view = c.view_mut();
+    let mut c_store = c_spec.wrap(&c_view);
+    be.iter(|| mm.run(&a_store, &b_store, &mut c_store, &[]).unwrap());
+}
+
+fn mmm_frame(c: &mut Criterion) {
+    // criterion reports a throughput in elements/s: elements counted here are
+    // flops, a multiplication and an addition per (m, k, n), so Gelem/s is GFLOP/s
+    let mut group = c.benchmark_group("mmm_frame_flops");
+    for (m, k, n) in shapes() {
+        group.throughput(Throughput::Elements((2 * m * k * n) as u64));
+        for &b_layout in &[BLayout::Packed, BLayout::Strides, BLayout::OffsetsAndPtrs] {
+            for &c_layout in &[CLayout::View, CLayout::Strides] {
+                group.bench_with_input(
+                    BenchmarkId::new(
+                        format!("b_{:?}_c_{:?}", b_layout, c_layout).to_lowercase(),
+                        format!("{}x{}x{}", m, k, n),
+                    ),
+                    &(m, k, n),
+                    |be, &shape| unsafe { run(be, shape, b_layout, c_layout) },
+                );
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, mmm_frame);
+criterion_main!(benches);
@@ -81,8 +81,10 @@ impl<TI: Copy> ScratchSpaceFusedNonLinear<TI> {
                     } else {
                         (down, K::mr())
                     };
+                    // on the edge tiles, only the valid part of the vector is copied, so
+                    // the kernel sees a zero-padded tile-sized vector
                     let have = v.len().saturating_sub(dir * r);
-                    let ptr = if have < K::mr() {
+                    let ptr = if have < r {
                         let mut buf = vec![TI::zero(); r];
                         if have > 0 {
                             buf[..have].copy_from_slice(&v.as_slice_unchecked()[dir * r..][..have]);
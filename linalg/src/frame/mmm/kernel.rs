@@ -28,6 +28,9 @@ impl LinearSpec {
     }
 }
 
+/// Kernel return code for an accumulator overflow, from kernels that check for it.
+pub const KERNEL_OVERFLOW: isize = 2;
+
 pub trait MatMatMulKer<TI>: Copy + Clone + Debug + Send + Sync + 'static
 where
     TI: Copy + Debug,
@@ -40,6 +43,11 @@ where
     fn end_padding_packed_a() -> usize;
     fn alignment_bytes_packed_b() -> usize;
     fn end_padding_packed_b() -> usize;
+    /// Whether the kernel reads B from `PanelStore::Strides` panels. If not,
+    /// a strided B is packed before running.
+    fn reads_strided_b() -> bool {
+        false
+    }
 }
 
 #[macro_export]
@@ -37,6 +37,7 @@ pub trait MatMatMul:
     ) -> MatrixStoreSpec;
     unsafe fn c_vec_from_data_and_stride(&self, stride: isize) -> MatrixStoreSpec;
     unsafe fn c_vec_from_data(&self) -> MatrixStoreSpec;
+    unsafe fn c_row_vec_from_data_and_stride(&self, stride: isize) -> MatrixStoreSpec;
 
     unsafe fn run(
         &self,
@@ -49,6 +50,15 @@ pub trait MatMatMul:
 
 dyn_clone::clone_trait_object!(MatMatMul);
 
+#[inline(always)]
+fn check_kernel_result(err: isize) -> anyhow::Result<()> {
+    match err {
+        0 => Ok(()),
+        KERNEL_OVERFLOW => anyhow::bail!("Accumulator overflow in matrix multiplication"),
+        _ => anyhow::bail!("Kernel return error {} (unsupported storage or fused op?)", err),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MatMatMulImpl<K, TA, TB, TC, TI>
 where
@@ -161,6 +171,7 @@ where
             byte_stride: stride * std::mem::size_of::<TB>() as isize,
             mr: K::mr(),
             nr: K::nr(),
+            orientation: VecStrideOrientation::Column,
         }
     }
 
@@ -192,6 +203,7 @@ where
             byte_stride: stride * std::mem::size_of::<TC>() as isize,
             mr: K::mr(),
             nr: K::nr(),
+            orientation: VecStrideOrientation::Column,
         }
     }
 
@@ -199,6 +211,15 @@ where
         self.c_vec_from_data_and_stride(1)
     }
 
+    unsafe fn c_row_vec_from_data_and_stride(&self, stride: isize) -> MatrixStoreSpec {
+        MatrixStoreSpec::VecStride {
+            byte_stride: stride * std::mem::size_of::<TC>() as isize,
+            mr: K::mr(),
+            nr: K::nr(),
+            orientation: VecStrideOrientation::Row,
+        }
+    }
+
     unsafe fn run(
         &self,
         a: &MatrixStore,
@@ -222,6 +243,37 @@ where
 
         let ref linear = LinearSpec::k(self.k);
         let non_linear = non_linear.to_vec();
+        let b_packed = if K::reads_strided_b() {
+            None
+        } else {
+            b.packed_panels_b(
+                nr,
+                0,
+                n,
+                self.k,
+                K::alignment_bytes_packed_b(),
+                K::end_padding_packed_b(),
+            )?
+        };
+        let b_packed_spec = self.b_packed();
+        let b_packed_view = b_packed.as_ref().map(|t| t.view());
+        let b_packed_store = b_packed_view.as_ref().map(|v| b_packed_spec.wrap(v));
+        let b = b_packed_store.as_ref().unwrap_or(b);
+        // a single column tail is read as a vector, a wider one is padded
+        let b_tail = if n % nr > 1 {
+            b.packed_panels_b(
+                nr,
+                n / nr,
+                n,
+                self.k,
+                K::alignment_bytes_packed_b(),
+                K::end_padding_packed_b(),
+            )?
+        } else {
+            None
+        };
+        let b_tail =
+            b_tail.as_ref().map(|t| PanelStore::Packed { ptr: t.as_ptr_unchecked::<u8>() as _ });
         // FIXME prefetch a are a bit weird
         for ia in 0..m / mr {
             let ref a = a.panel_a(ia);
@@ -244,9 +296,12 @@ where
                     linear,
                     non_linear,
                 });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                check_kernel_result(err)?;
             }
-            if let MatrixStoreSpec::VecStride { .. } = c.spec {
+            if let MatrixStoreSpec::VecStride {
+                orientation: VecStrideOrientation::Column, ..
+            } = c.spec
+            {
                 if let PanelStore::Packed { ptr } = a {
                     prefetch(*ptr as *const u8, 512);
                 }
@@ -264,12 +319,12 @@ where
                     linear,
                     non_linear,
                 });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                check_kernel_result(err)?;
             } else if n % nr != 0 {
                 if let PanelStore::Packed { ptr } = a {
                     prefetch(*ptr as *const u8, 512);
                 }
-                let ref b = b.panel_b(nr, n / nr, n % nr);
+                let ref b = b_tail.unwrap_or_else(|| b.panel_b(nr, n / nr, n));
                 match b {
                     PanelStore::Packed { ptr } => prefetch(*ptr as *const u8, 512),
                     PanelStore::VecStride { ptr, .. } => prefetch(*ptr as *const u8, 128),
@@ -284,7 +339,7 @@ where
                     linear,
                     non_linear,
                 });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                check_kernel_result(err)?;
                 c.set_from_tile::<TC>(ia, n / nr, mr, n % nr, tmpc.tensor, mr, nr);
             }
         }
@@ -309,7 +364,7 @@ where
                     linear,
                     non_linear,
                 });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                check_kernel_result(err)?;
                 c.set_from_tile::<TC>(m / mr, ib, m % mr, nr, tmpc.tensor, mr, nr);
             }
             if n % nr != 0 {
@@ -317,7 +372,7 @@ where
                 if let PanelStore::Packed { ptr } = panel_a {
                     prefetch(*ptr as *const u8, 512);
                 }
-                let ref b = b.panel_b(nr, n / nr, n % nr);
+                let ref b = b_tail.unwrap_or_else(|| b.panel_b(nr, n / nr, n));
                 match b {
                     PanelStore::Packed { ptr } => prefetch(*ptr as *const u8, 512),
                     PanelStore::VecStride { ptr, .. } => prefetch(*ptr as *const u8, 128),
@@ -331,7 +386,7 @@ where
                     linear,
                     non_linear,
                 });
-                debug_assert_eq!(err, 0, "Kernel return error {}", err);
+                check_kernel_result(err)?;
                 c.set_from_tile::<TC>(m / mr, n / nr, m % mr, n % nr, tmpc.tensor, mr, nr);
             }
         }
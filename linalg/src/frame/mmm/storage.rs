@@ -1,6 +1,7 @@
 use std::ffi::c_void;
 use std::fmt;
 use std::fmt::Debug;
+use tract_data::internal::tract_ndarray::ArrayD;
 use tract_data::internal::*;
 
 #[derive(PartialEq, Clone, Debug, Hash)]
@@ -9,10 +10,44 @@ pub enum MatrixStoreSpec {
     Packed { panel_len: usize },
     Strides { row_byte_stride: isize, col_byte_stride: isize },
     OffsetsAndPtrs { row_byte_offsets: Vec<isize>, col_byte_offsets: Vec<isize>, nr: usize },
-    VecStride { byte_stride: isize, mr: usize, nr: usize },
+    VecStride { byte_stride: isize, mr: usize, nr: usize, orientation: VecStrideOrientation },
+}
+
+/// Which way a `VecStride` C vector goes: a `Column` (m x 1) advances by its
+/// stride from row to row, a `Row` (1 x n) from column to column.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Hash)]
+pub enum VecStrideOrientation {
+    Column,
+    Row,
 }
 
 impl MatrixStoreSpec {
+    /// Checked `Packed` spec for `mn` rows (or columns) packed by panels of
+    /// `mr` over `k`, in a tensor of `tensor_len` items.
+    pub fn packed(
+        tensor_len: usize,
+        mr: usize,
+        mn: usize,
+        k: usize,
+    ) -> tract_data::anyhow::Result<MatrixStoreSpec> {
+        if mr == 0 {
+            tract_data::anyhow::bail!("Packed storage needs non-empty panels")
+        }
+        let panel_len = mr * k;
+        let wanted = (mn + mr - 1) / mr * panel_len;
+        if tensor_len < wanted {
+            tract_data::anyhow::bail!(
+                "Packed storage for {} x {} by panels of {} needs {} items, got {}",
+                mn,
+                k,
+                mr,
+                wanted,
+                tensor_len
+            )
+        }
+        Ok(MatrixStoreSpec::Packed { panel_len })
+    }
+
     pub unsafe fn wrap<'t>(&self, tensor: &'t TensorView) -> MatrixStore<'_, 't> {
         MatrixStore::new(self, tensor)
     }
@@ -89,10 +124,113 @@ impl<'s, 't> MatrixStore<'s, 't> {
                 byte_stride: *byte_stride,
                 item_size: dt.size_of(),
             },
-            _ => unimplemented!(),
+            MatrixStoreSpec::Strides { .. } | MatrixStoreSpec::View { .. } => {
+                let (row_byte_stride, col_byte_stride) = self.strides();
+                let ptr = ptr.offset(col_byte_stride * (nr * i) as isize);
+                if nr * i + 1 == n {
+                    PanelStore::VecStride {
+                        ptr: ptr as _,
+                        byte_stride: row_byte_stride,
+                        item_size: dt.size_of(),
+                    }
+                } else {
+                    PanelStore::Strides {
+                        ptr: ptr as _,
+                        row_byte_stride,
+                        col_byte_stride,
+                        item_size: dt.size_of(),
+                    }
+                }
+            }
         }
     }
 
+    /// Copy the B panels from the `i`-th one (starting at column `nr * i` of
+    /// `n`) into packed panels, the last one padded with zeros. Used for
+    /// kernels that can not read a strided B, and for a partial last panel
+    /// which a kernel would read past the end of B.
+    ///
+    /// Returns None for non strided stores, which can be read in place.
+    pub(super) unsafe fn packed_panels_b(
+        &self,
+        nr: usize,
+        i: usize,
+        n: usize,
+        k: usize,
+        alignment: usize,
+        end_padding: usize,
+    ) -> tract_data::anyhow::Result<Option<Tensor>> {
+        match self.spec {
+            MatrixStoreSpec::Strides { .. } | MatrixStoreSpec::View { .. } => (),
+            _ => return Ok(None),
+        }
+        let dt = self.tensor.datum_type();
+        let item_size = dt.size_of();
+        let (row_byte_stride, col_byte_stride) = self.strides();
+        let ptr = self.tensor.as_ptr_unchecked::<u8>();
+        let panels = (n - nr * i + nr - 1) / nr;
+        let mut packed =
+            Tensor::zero_aligned_dt(dt, &[(panels * k + end_padding) * nr], alignment)?;
+        let dst = packed.as_ptr_mut_unchecked::<u8>();
+        for col in 0..n - nr * i {
+            let panel = dst.add((col / nr * k * nr + col % nr) * item_size);
+            for row in 0..k {
+                std::ptr::copy_nonoverlapping(
+                    ptr.offset(
+                        row_byte_stride * row as isize + col_byte_stride * (nr * i + col) as isize,
+                    ),
+                    panel.add(row * nr * item_size),
+                    item_size,
+                );
+            }
+        }
+        Ok(Some(packed))
+    }
+
+    /// Read back the `i`-th A panel (`k` x `mr`) as a kernel would see it.
+    pub unsafe fn dump_panel_a<T: Datum + Copy>(&self, i: usize, mr: usize, k: usize) -> ArrayD<T> {
+        self.panel_a(i).dump(k, mr)
+    }
+
+    /// Read back the `i`-th B panel (`k` x `nr`, B being `n` columns wide) as a
+    /// kernel would see it.
+    pub unsafe fn dump_panel_b<T: Datum + Copy>(
+        &self,
+        nr: usize,
+        i: usize,
+        n: usize,
+        k: usize,
+    ) -> ArrayD<T> {
+        self.panel_b(nr, i, n).dump(k, nr)
+    }
+
+    /// Repack a strided (`View`, `Strides` or `VecStride`) matrix of `mn` rows
+    /// and `k` columns into panels of `mr` rows, in the order kernels consume
+    /// packed A: each panel holds `mr` values for every k, the last one being
+    /// padded with zeros.
+    ///
+    /// A B matrix is repacked the same way once wrapped with its row and
+    /// column strides swapped. The buffer is not aligned, it must be copied to
+    /// a tensor before use.
+    pub unsafe fn repack(&self, mr: usize, mn: usize, k: usize) -> (Vec<u8>, MatrixStoreSpec) {
+        let item_size = self.tensor.datum_type().size_of();
+        let (row_byte_stride, col_byte_stride) = self.strides();
+        let ptr = self.tensor.as_ptr_unchecked::<u8>();
+        let panel_len = mr * k;
+        let mut buffer = vec![0u8; (mn + mr - 1) / mr * panel_len * item_size];
+        for row in 0..mn {
+            let (panel, r) = (row / mr, row % mr);
+            for col in 0..k {
+                std::ptr::copy_nonoverlapping(
+                    ptr.offset(row_byte_stride * row as isize + col_byte_stride * col as isize),
+                    buffer.as_mut_ptr().add((panel * panel_len + col * mr + r) * item_size),
+                    item_size,
+                );
+            }
+        }
+        (buffer, MatrixStoreSpec::Packed { panel_len })
+    }
+
     #[inline]
     unsafe fn strides(&self) -> (isize, isize) {
         match self.spec {
@@ -113,7 +251,10 @@ impl<'s, 't> MatrixStore<'s, 't> {
             MatrixStoreSpec::Strides { row_byte_stride, col_byte_stride } => {
                 (*row_byte_stride, *col_byte_stride)
             }
-            MatrixStoreSpec::VecStride { byte_stride, .. } => (*byte_stride, 0),
+            MatrixStoreSpec::VecStride { byte_stride, orientation, .. } => match orientation {
+                VecStrideOrientation::Column => (*byte_stride, 0),
+                VecStrideOrientation::Row => (0, *byte_stride),
+            },
             _ => panic!(),
         }
     }
@@ -138,7 +279,7 @@ impl<'s, 't> MatrixStore<'s, 't> {
                     item_size: self.tensor.datum_type().size_of(),
                 }
             }
-            MatrixStoreSpec::VecStride { .. } => {
+            MatrixStoreSpec::VecStride { orientation: VecStrideOrientation::Column, .. } => {
                 let ptr = self.tensor.as_ptr_unchecked::<u8>();
                 let (row_byte_stride, _col_byte_stride) = self.strides();
                 PanelStore::VecStride {
@@ -147,6 +288,21 @@ impl<'s, 't> MatrixStore<'s, 't> {
                     item_size: self.tensor.datum_type().size_of(),
                 }
             }
+            MatrixStoreSpec::VecStride {
+                byte_stride,
+                orientation: VecStrideOrientation::Row,
+                ..
+            } => {
+                // only valid for a single row tile (mr == 1): the other rows
+                // would all be written to the same place
+                let ptr = self.tensor.as_ptr_unchecked::<u8>();
+                PanelStore::Strides {
+                    ptr: ptr.offset(byte_stride * right * nr) as *mut _,
+                    row_byte_stride: 0,
+                    col_byte_stride: *byte_stride,
+                    item_size: self.tensor.datum_type().size_of(),
+                }
+            }
             _ => unimplemented!(),
         }
     }
@@ -161,13 +317,13 @@ impl<'s, 't> MatrixStore<'s, 't> {
         mr: usize,
         nr: usize,
     ) {
-        let (row_byte_stride, col_byte_stride) = self.strides();
-        let mut dst = self.tensor.as_ptr_unchecked::<u8>().offset(
-            (row_byte_stride as usize * (down * mr) + col_byte_stride as usize * (right * nr))
-                as isize,
-        );
         match self.spec {
             MatrixStoreSpec::Strides { .. } | MatrixStoreSpec::View { .. } => {
+                let (row_byte_stride, col_byte_stride) = self.strides();
+                let mut dst = self.tensor.as_ptr_unchecked::<u8>().offset(
+                    row_byte_stride * (down * mr) as isize
+                        + col_byte_stride * (right * nr) as isize,
+                );
                 for y in 0..height {
                     let mut row_dst = dst;
                     for x in 0..width {
@@ -178,7 +334,39 @@ impl<'s, 't> MatrixStore<'s, 't> {
                     dst = dst.offset(row_byte_stride);
                 }
             }
-            MatrixStoreSpec::VecStride { .. } => {
+            MatrixStoreSpec::OffsetsAndPtrs { row_byte_offsets, .. } => {
+                let col_ptrs = self.col_ptrs.as_ref().unwrap();
+                for x in 0..width {
+                    let col = *col_ptrs.get_unchecked(right * nr + x);
+                    for y in 0..height {
+                        let value = *tile.as_ptr_unchecked::<T>().offset((y + x * mr) as isize);
+                        let offset = *row_byte_offsets.get_unchecked(down * mr + y);
+                        *(col.offset(offset) as *mut T) = value;
+                    }
+                }
+            }
+            MatrixStoreSpec::VecStride {
+                byte_stride,
+                orientation: VecStrideOrientation::Row,
+                ..
+            } => {
+                debug_assert!(down == 0 && height == 1);
+                let mut dst = self
+                    .tensor
+                    .as_ptr_unchecked::<u8>()
+                    .offset(byte_stride * (right * nr) as isize);
+                for x in 0..width {
+                    let value = *tile.as_ptr_unchecked::<T>().offset((x * mr) as isize);
+                    *(dst as *mut T) = value;
+                    dst = dst.offset(*byte_stride);
+                }
+            }
+            MatrixStoreSpec::VecStride { orientation: VecStrideOrientation::Column, .. } => {
+                let (row_byte_stride, _) = self.strides();
+                let mut dst = self
+                    .tensor
+                    .as_ptr_unchecked::<u8>()
+                    .offset(row_byte_stride * (down * mr) as isize);
                 for y in 0..height {
                     let value = *tile.as_ptr_unchecked::<T>().offset(y as isize);
                     *(dst as *mut T) = value;
@@ -198,3 +386,472 @@ pub enum PanelStore {
     OffsetsAndPtrs { row_byte_offsets: *const isize, col_ptrs: *const *const c_void },
     VecStride { ptr: *const c_void, byte_stride: isize, item_size: usize },
 }
+
+impl PanelStore {
+    /// Materialize a `k` x `width` panel, walking it the way kernels do.
+    ///
+    /// This is meant for debugging packing and layout issues. A `VecStride`
+    /// panel is a single column, whatever `width` is.
+    pub unsafe fn dump<T: Datum + Copy>(&self, k: usize, width: usize) -> ArrayD<T> {
+        match *self {
+            PanelStore::Packed { ptr } => {
+                let ptr = ptr as *const T;
+                ArrayD::from_shape_fn(&[k, width][..], |ix| *ptr.add(ix[0] * width + ix[1]))
+            }
+            PanelStore::Strides { ptr, row_byte_stride, col_byte_stride, .. } => {
+                let ptr = ptr as *const u8;
+                ArrayD::from_shape_fn(&[k, width][..], |ix| {
+                    *(ptr
+                        .offset(row_byte_stride * ix[0] as isize + col_byte_stride * ix[1] as isize)
+                        as *const T)
+                })
+            }
+            PanelStore::OffsetsAndPtrs { row_byte_offsets, col_ptrs } => {
+                ArrayD::from_shape_fn(&[k, width][..], |ix| {
+                    let col = *col_ptrs.add(ix[1]) as *const u8;
+                    *(col.offset(*row_byte_offsets.add(ix[0])) as *const T)
+                })
+            }
+            PanelStore::VecStride { ptr, byte_stride, .. } => {
+                let ptr = ptr as *const u8;
+                ArrayD::from_shape_fn(&[k, 1][..], |ix| {
+                    *(ptr.offset(byte_stride * ix[0] as isize) as *const T)
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::Packer;
+    use proptest::prelude::*;
+    use tract_data::internal::tract_ndarray::{s, Array2};
+
+    /// Items around the matrix, and value they hold. Matrix values are all
+    /// positive, so any read or write outside of the matrix shows up.
+    const GUARD: usize = 8;
+    const SENTINEL: f32 = -1.0;
+
+    #[derive(Clone, Debug)]
+    enum Layout {
+        View { transposed: bool },
+        Strides { row: isize, col: isize },
+        Packed { is_a: bool },
+        VecStride { stride: isize, orientation: VecStrideOrientation },
+    }
+
+    /// A `rows` x `cols` f32 matrix in some storage, and the `mr` x `nr` tile
+    /// (or panel) at `down`, `right` to access.
+    #[derive(Clone, Debug)]
+    struct StorageProblem {
+        rows: usize,
+        cols: usize,
+        layout: Layout,
+        mr: usize,
+        nr: usize,
+        down: usize,
+        right: usize,
+    }
+
+    impl StorageProblem {
+        fn value(&self, row: usize, col: usize) -> f32 {
+            (row * self.cols + col) as f32
+        }
+
+        /// Spec, item offset of the matrix origin in the buffer, buffer length
+        /// and buffer index of every matrix item.
+        fn storage(&self) -> (MatrixStoreSpec, usize, usize, Array2<usize>) {
+            let (rows, cols) = (self.rows, self.cols);
+            let strided = |row: isize, col: isize| {
+                let min = row.min(0) * (rows - 1) as isize + col.min(0) * (cols - 1) as isize;
+                let origin = GUARD as isize - min;
+                let cells = Array2::from_shape_fn((rows, cols), |(r, c)| {
+                    (origin + row * r as isize + col * c as isize) as usize
+                });
+                (origin as usize, cells)
+            };
+            let (spec, origin, cells) = match self.layout {
+                Layout::View { transposed } => {
+                    let spec = MatrixStoreSpec::View {
+                        axes: if transposed { Some((1, 0)) } else { None },
+                    };
+                    let (row, col) =
+                        if transposed { (1, rows as isize + 2) } else { (cols as isize + 2, 1) };
+                    let (origin, cells) = strided(row, col);
+                    (spec, origin, cells)
+                }
+                Layout::Strides { row, col } => {
+                    let spec = MatrixStoreSpec::Strides {
+                        row_byte_stride: row * 4,
+                        col_byte_stride: col * 4,
+                    };
+                    let (origin, cells) = strided(row, col);
+                    (spec, origin, cells)
+                }
+                Layout::VecStride { stride, orientation } => {
+                    let spec = MatrixStoreSpec::VecStride {
+                        byte_stride: stride * 4,
+                        mr: self.mr,
+                        nr: self.nr,
+                        orientation,
+                    };
+                    let (origin, cells) = match orientation {
+                        VecStrideOrientation::Column => strided(stride, 0),
+                        VecStrideOrientation::Row => strided(0, stride),
+                    };
+                    (spec, origin, cells)
+                }
+                Layout::Packed { is_a: true } => {
+                    let panel_len = self.mr * cols;
+                    let cells = Array2::from_shape_fn((rows, cols), |(r, c)| {
+                        GUARD + r / self.mr * panel_len + c * self.mr + r % self.mr
+                    });
+                    (MatrixStoreSpec::Packed { panel_len }, GUARD, cells)
+                }
+                Layout::Packed { is_a: false } => {
+                    let panel_len = self.nr * rows;
+                    let cells = Array2::from_shape_fn((rows, cols), |(r, c)| {
+                        GUARD + c / self.nr * panel_len + r * self.nr + c % self.nr
+                    });
+                    (MatrixStoreSpec::Packed { panel_len }, GUARD, cells)
+                }
+            };
+            let len = match &spec {
+                MatrixStoreSpec::Packed { panel_len } => {
+                    let panels = if let Layout::Packed { is_a: true } = self.layout {
+                        (rows + self.mr - 1) / self.mr
+                    } else {
+                        (cols + self.nr - 1) / self.nr
+                    };
+                    2 * GUARD + panels * panel_len
+                }
+                _ => cells.iter().max().unwrap() + 1 + GUARD,
+            };
+            (spec, origin, len, cells)
+        }
+
+        fn check(&self) {
+            let (spec, origin, len, cells) = self.storage();
+            let mut buffer = vec![SENTINEL; len];
+            for ((r, c), &ix) in cells.indexed_iter() {
+                buffer[ix] = self.value(r, c);
+            }
+            let mut buffer = tensor1(&buffer);
+            let (shape, strides) = if let Layout::View { transposed: true } = self.layout {
+                ([self.cols, self.rows], [self.rows as isize + 2, 1])
+            } else {
+                ([self.rows, self.cols], [self.cols as isize + 2, 1])
+            };
+            let valid_rows = (self.rows - self.down * self.mr).min(self.mr);
+            let valid_cols = (self.cols - self.right * self.nr).min(self.nr);
+            let view =
+                unsafe { TensorView::from_bytes(&buffer, origin as isize * 4, &shape, &strides) };
+            let mut store = unsafe { spec.wrap(&view) };
+            let row_vec = match self.layout {
+                Layout::VecStride { orientation: VecStrideOrientation::Row, .. } => true,
+                _ => false,
+            };
+
+            if let Layout::Packed { is_a: true } = self.layout {
+                let found = unsafe { store.panel_a(self.down).dump::<f32>(self.cols, self.mr) };
+                for x in 0..valid_rows {
+                    for k in 0..self.cols {
+                        assert_eq!(found[[k, x]], self.value(self.down * self.mr + x, k));
+                    }
+                }
+                return;
+            }
+
+            if !row_vec {
+                let packed = match self.layout {
+                    Layout::Packed { .. } => true,
+                    _ => false,
+                };
+                let found = unsafe {
+                    store
+                        .panel_b(self.nr, self.right, self.cols)
+                        .dump::<f32>(self.rows, if packed { self.nr } else { valid_cols })
+                };
+                for x in 0..found.shape()[1].min(valid_cols) {
+                    for k in 0..self.rows {
+                        assert_eq!(found[[k, x]], self.value(k, self.right * self.nr + x));
+                    }
+                }
+                if packed {
+                    return;
+                }
+            }
+
+            let found = unsafe {
+                store
+                    .tile_c(self.down, self.right, self.mr, self.nr)
+                    .dump::<f32>(valid_rows, valid_cols)
+            };
+            for y in 0..valid_rows {
+                for x in 0..found.shape()[1] {
+                    assert_eq!(
+                        found[[y, x]],
+                        self.value(self.down * self.mr + y, self.right * self.nr + x)
+                    );
+                }
+            }
+
+            // column major tile, as kernels leave it
+            let tile =
+                tensor1(&(0..self.mr * self.nr).map(|i| 1000. + i as f32).collect::<Vec<_>>());
+            unsafe {
+                store.set_from_tile::<f32>(
+                    self.down,
+                    self.right,
+                    valid_rows,
+                    valid_cols,
+                    &tile.view(),
+                    self.mr,
+                    self.nr,
+                )
+            };
+            let mut expected = vec![SENTINEL; len];
+            for ((r, c), &ix) in cells.indexed_iter() {
+                let (y, x) =
+                    (r.wrapping_sub(self.down * self.mr), c.wrapping_sub(self.right * self.nr));
+                expected[ix] = if y < valid_rows && x < valid_cols {
+                    1000. + (y + x * self.mr) as f32
+                } else {
+                    self.value(r, c)
+                };
+            }
+            assert_eq!(buffer.as_slice_mut::<f32>().unwrap(), &*expected);
+        }
+    }
+
+    impl Arbitrary for StorageProblem {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<StorageProblem>;
+        fn arbitrary_with(_args: ()) -> Self::Strategy {
+            (1usize..8, 1usize..8, 1usize..5, 1usize..5)
+                .prop_flat_map(|(rows, cols, mr, nr)| {
+                    // non overlapping: the outer stride skips over the inner axis
+                    let strides =
+                        (any::<bool>(), 1isize..3, 0isize..3, any::<bool>(), any::<bool>())
+                            .prop_map(move |(col_major, step, pad, neg_row, neg_col)| {
+                                let inner_len = if col_major { rows } else { cols } as isize;
+                                let (inner, outer) = (step, step * inner_len + pad);
+                                let (row, col) =
+                                    if col_major { (inner, outer) } else { (outer, inner) };
+                                Layout::Strides {
+                                    row: if neg_row { -row } else { row },
+                                    col: if neg_col { -col } else { col },
+                                }
+                            });
+                    let vec =
+                        (1isize..3, any::<bool>(), any::<bool>()).prop_map(|(stride, neg, row)| {
+                            Layout::VecStride {
+                                stride: if neg { -stride } else { stride },
+                                orientation: if row {
+                                    VecStrideOrientation::Row
+                                } else {
+                                    VecStrideOrientation::Column
+                                },
+                            }
+                        });
+                    let layout = prop_oneof![
+                        any::<bool>().prop_map(|transposed| Layout::View { transposed }),
+                        strides,
+                        any::<bool>().prop_map(|is_a| Layout::Packed { is_a }),
+                        vec,
+                    ];
+                    (Just((rows, cols, mr, nr)), layout)
+                })
+                .prop_flat_map(|((rows, cols, mr, nr), layout)| {
+                    let (rows, cols) = match layout {
+                        Layout::VecStride { orientation: VecStrideOrientation::Column, .. } => {
+                            (rows, 1)
+                        }
+                        Layout::VecStride { orientation: VecStrideOrientation::Row, .. } => {
+                            (1, cols)
+                        }
+                        _ => (rows, cols),
+                    };
+                    (
+                        Just((rows, cols, mr, nr, layout)),
+                        0..(rows + mr - 1) / mr,
+                        0..(cols + nr - 1) / nr,
+                    )
+                })
+                .prop_map(|((rows, cols, mr, nr, layout), down, right)| StorageProblem {
+                    rows,
+                    cols,
+                    layout,
+                    mr,
+                    nr,
+                    down,
+                    right,
+                })
+                .boxed()
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop(pb in any::<StorageProblem>()) {
+            pb.check()
+        }
+    }
+
+    #[test]
+    fn negative_strides_partial_tile() {
+        StorageProblem {
+            rows: 3,
+            cols: 3,
+            layout: Layout::Strides { row: -1, col: -4 },
+            mr: 2,
+            nr: 2,
+            down: 1,
+            right: 1,
+        }
+        .check()
+    }
+
+    #[test]
+    fn row_vec_tile() {
+        StorageProblem {
+            rows: 1,
+            cols: 5,
+            layout: Layout::VecStride { stride: 2, orientation: VecStrideOrientation::Row },
+            mr: 3,
+            nr: 2,
+            down: 0,
+            right: 2,
+        }
+        .check()
+    }
+
+    #[test]
+    fn checked_packed() {
+        let packer = Packer::new(3, 2, 4, 0);
+        assert_eq!(
+            MatrixStoreSpec::packed(packer.len(5), 2, 5, 3).unwrap(),
+            MatrixStoreSpec::Packed { panel_len: 6 }
+        );
+        assert!(MatrixStoreSpec::packed(packer.len(5) - 1, 2, 5, 3).is_err());
+    }
+
+    #[test]
+    fn dump_packed_panel_b() {
+        let b = Array2::from_shape_fn((3, 5), |(k, n)| (10 * k + n) as f32);
+        let packer = Packer::new(3, 2, 4, 0);
+        let mut pb = Tensor::zero::<f32>(&[packer.len(5)]).unwrap();
+        unsafe { packer.pack(pb.view_mut(), b.clone().into_tensor().view(), 0, 1) };
+        let spec = MatrixStoreSpec::Packed { panel_len: 3 * 2 };
+        let view = pb.view();
+        let store = unsafe { spec.wrap(&view) };
+        for i in 0..2 {
+            assert_eq!(
+                unsafe { store.dump_panel_b::<f32>(2, i, 5, 3) },
+                b.slice(s![.., 2 * i..2 * i + 2]).into_dyn()
+            );
+        }
+        assert_eq!(
+            unsafe { store.dump_panel_b::<f32>(2, 2, 5, 3) },
+            b.slice(s![.., 4..5]).into_dyn()
+        );
+    }
+
+    #[test]
+    fn dump_strided_panel_b() {
+        let b = Array2::from_shape_fn((3, 4), |(k, n)| (10 * k + n) as f32);
+        let t = b.clone().into_tensor();
+        let spec = MatrixStoreSpec::View { axes: None };
+        let view = t.view();
+        let store = unsafe { spec.wrap(&view) };
+        assert_eq!(
+            unsafe { store.dump_panel_b::<f32>(2, 1, 4, 3) },
+            b.slice(s![.., 2..4]).into_dyn()
+        );
+    }
+
+    #[test]
+    fn repack_strided_a() {
+        // m=5, k=3, column major
+        let a = Array2::from_shape_fn((5, 3), |(m, k)| (10 * m + k) as f32);
+        let t = a.t().to_owned().into_tensor();
+        let view = t.view();
+        let spec = MatrixStoreSpec::Strides { row_byte_stride: 4, col_byte_stride: 20 };
+        let (buffer, packed) = unsafe { spec.wrap(&view).repack(2, 5, 3) };
+        assert_eq!(packed, MatrixStoreSpec::Packed { panel_len: 6 });
+        let pa = unsafe { Tensor::from_raw_dt(f32::datum_type(), &[buffer.len() / 4], &buffer) }
+            .unwrap();
+        let view = pa.view();
+        let store = unsafe { packed.wrap(&view) };
+        for i in 0..2 {
+            assert_eq!(
+                unsafe { store.dump_panel_a::<f32>(i, 2, 3) },
+                a.slice(s![2 * i..2 * i + 2, ..]).t().into_dyn()
+            );
+        }
+        let mut last = Array2::zeros((3, 2));
+        last.column_mut(0).assign(&a.row(4));
+        assert_eq!(unsafe { store.dump_panel_a::<f32>(2, 2, 3) }, last.into_dyn());
+    }
+
+    #[test]
+    fn panel_b_strides() {
+        // k=3, n=5, row major
+        let b = Tensor::zero::<f32>(&[3, 5]).unwrap();
+        let view = b.view();
+        let ptr = b.as_ptr::<f32>().unwrap() as *const u8;
+        for spec in &[
+            MatrixStoreSpec::View { axes: None },
+            MatrixStoreSpec::Strides { row_byte_stride: 20, col_byte_stride: 4 },
+        ] {
+            let store = unsafe { spec.wrap(&view) };
+            for i in 0..2 {
+                assert_eq!(
+                    unsafe { store.panel_b(2, i, 5) },
+                    PanelStore::Strides {
+                        ptr: unsafe { ptr.offset(8 * i as isize) } as _,
+                        row_byte_stride: 20,
+                        col_byte_stride: 4,
+                        item_size: 4
+                    }
+                );
+            }
+            assert_eq!(
+                unsafe { store.panel_b(2, 2, 5) },
+                PanelStore::VecStride {
+                    ptr: unsafe { ptr.offset(16) } as _,
+                    byte_stride: 20,
+                    item_size: 4
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn set_from_tile_offsets_and_ptrs() {
+        let mut c = Tensor::zero::<f32>(&[32]).unwrap();
+        let f32_offsets = |v: &[isize]| v.iter().map(|x| x * 4).collect::<Vec<isize>>();
+        let spec = MatrixStoreSpec::OffsetsAndPtrs {
+            row_byte_offsets: f32_offsets(&[0, 2, 5, 9, 11, 0, 0, 0]),
+            col_byte_offsets: f32_offsets(&[0, 1, 13, 20]),
+            nr: 2,
+        };
+        // 4x2 tile, column major: value at (y, x) is 1 + y + 4 * x
+        let tile = tensor1(&(1..=8).map(|x| x as f32).collect::<Vec<_>>());
+        let view = c.view_mut();
+        let mut store = unsafe { spec.wrap(&view) };
+        unsafe {
+            store.set_from_tile::<f32>(0, 0, 2, 2, &tile.view(), 4, 2);
+            store.set_from_tile::<f32>(1, 1, 1, 2, &tile.view(), 4, 2);
+        }
+        let mut expected = vec![0f32; 32];
+        for &(row, col, v) in
+            &[(0, 0, 1.), (2, 0, 2.), (0, 1, 5.), (2, 1, 6.), (11, 13, 1.), (11, 20, 5.)]
+        {
+            expected[row + col] = v;
+        }
+        assert_eq!(c.as_slice::<f32>().unwrap(), &*expected);
+    }
+}
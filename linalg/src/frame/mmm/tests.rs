@@ -159,6 +159,16 @@ macro_rules! mmm_frame_tests {
                 }
             }
 
+            #[test]
+            fn vec_mat_1() {
+                if $cond {
+                    let a = tensor2(&[[1, 2]]).cast_to::<$ta>().unwrap().into_owned();
+                    let b = tensor2(&[[1, 0, 1, 2, 0, 3, 1], [0, 1, 1, 0, 3, 1, 2]]);
+                    let b = b.cast_to::<$tb>().unwrap().into_owned();
+                    test_vec_mat_mul_prep::<$ker, $ta, $tb, $tc, $ti>(2, 7, &a, &b).unwrap()
+                }
+            }
+
             #[test]
             fn row_mul_2_1_3() {
                 if $cond {
@@ -187,6 +197,20 @@ macro_rules! mmm_frame_tests {
                 }
             }
 
+            #[test]
+            fn col_add_17_3_13() {
+                if $cond {
+                    unsafe { col_add::<$ker, $ta, $tb, $tc, $ti>(17, 3, 13).unwrap() }
+                }
+            }
+
+            #[test]
+            fn row_add_17_3_13() {
+                if $cond {
+                    unsafe { row_add::<$ker, $ta, $tb, $tc, $ti>(17, 3, 13).unwrap() }
+                }
+            }
+
             #[test]
             fn max_2_1_3() {
                 if $cond {
@@ -200,6 +224,20 @@ macro_rules! mmm_frame_tests {
                     unsafe { min::<$ker, $ta, $tb, $tc, $ti>(2, 3, 3).unwrap() }
                 }
             }
+
+            #[test]
+            fn relu_17_7_13() {
+                if $cond {
+                    unsafe { relu::<$ker, $ta, $tb, $tc, $ti>(17, 7, 13).unwrap() }
+                }
+            }
+
+            #[test]
+            fn clamp_17_7_13() {
+                if $cond {
+                    unsafe { clamp::<$ker, $ta, $tb, $tc, $ti>(17, 7, 13).unwrap() }
+                }
+            }
         }
     };
 }
@@ -362,6 +400,59 @@ where
     }
 }
 
+/// Multiply a 1 x k row by a k x n matrix, writing the 1 x n result every
+/// other item of C.
+pub fn test_vec_mat_mul_prep<K: MatMatMulKer<TI> + 'static, TA, TB, TC, TI>(
+    k: usize,
+    n: usize,
+    a: &Tensor,
+    b: &Tensor,
+) -> Result<(), proptest::test_runner::TestCaseError>
+where
+    TA: LADatum + AsPrimitive<TI> + 'static,
+    TB: LADatum + AsPrimitive<TI> + 'static,
+    TC: LADatum + AsPrimitive<TI> + 'static,
+    TI: LADatum + AsPrimitive<TC> + 'static + Neg<Output = TI>,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    unsafe {
+        let op = MatMatMulImpl::<K, TA, TB, TC, TI>::new(1, k, n);
+        let mut packed_a =
+            Tensor::uninitialized_aligned::<TA>(&[op.a_pack().len(1)], op.a_pack().alignment())
+                .unwrap();
+        op.a_pack().pack(packed_a.view_mut(), a.view(), 1, 0);
+
+        let mut packed_b =
+            Tensor::uninitialized_aligned::<TB>(&[op.b_pack().len(n)], op.b_pack().alignment())
+                .unwrap();
+        op.b_pack().pack(packed_b.view_mut(), b.view(), 0, 1);
+
+        let mut found = tensor0(TC::max_value()).broadcast_scalar_to_shape(&[2 * n]).unwrap();
+
+        op.run(
+            &op.a_packed().wrap(&packed_a.view()),
+            &op.b_packed().wrap(&packed_b.view()),
+            &mut op.c_row_vec_from_data_and_stride(2).wrap(&found.view_mut()),
+            &[],
+        )
+        .unwrap();
+
+        let mut expected = tensor0(TC::max_value()).broadcast_scalar_to_shape(&[2 * n]).unwrap();
+        for x in 0..n {
+            let mut v: TI = TI::zero();
+            for i in 0..k {
+                let a: TI = a.as_slice::<TA>().unwrap()[i].as_();
+                let b: TI = b.as_slice::<TB>().unwrap()[x + i * n].as_();
+                v = v + a * b;
+            }
+            expected.as_slice_mut::<TC>().unwrap()[2 * x] = v.as_();
+        }
+        found.close_enough(&expected, true).unwrap();
+        Ok(())
+    }
+}
+
 pub unsafe fn fused_op<K: MatMatMulKer<TI> + 'static, TA, TB, TC, TI, F: Fn(&mut [TI])>(
     m: usize,
     k: usize,
@@ -378,6 +469,24 @@ where
     usize: AsPrimitive<TI>,
 {
     let a = tensor1(&*vec![TA::one(); m * k]).into_shape(&[m, k]).unwrap();
+    fused_op_with_a::<K, TA, TB, TC, TI, F>(&a, n, spec, expect)
+}
+
+pub unsafe fn fused_op_with_a<K: MatMatMulKer<TI> + 'static, TA, TB, TC, TI, F: Fn(&mut [TI])>(
+    a: &Tensor,
+    n: usize,
+    spec: &[FusedSpec],
+    expect: F,
+) -> proptest::test_runner::TestCaseResult
+where
+    TA: LADatum + AsPrimitive<TI> + 'static,
+    TB: LADatum + AsPrimitive<TI> + 'static,
+    TC: LADatum + AsPrimitive<TI> + 'static,
+    TI: LADatum + AsPrimitive<TC> + 'static + Neg<Output = TI>,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    let (m, k) = (a.shape()[0], a.shape()[1]);
     let b = tensor1(&*vec![TB::one(); k * n]).into_shape(&[k, n]).unwrap();
     let op = MatMatMulImpl::<K, TA, TB, TC, TI>::new(m, k, n);
 
@@ -549,6 +658,62 @@ where
     })
 }
 
+/// A with values in -1..=1, so the product gets both signs
+fn signed_a<TA: Datum>(m: usize, k: usize) -> Tensor {
+    let a: Vec<i32> = (0..m * k).map(|i| (i % 3) as i32 - 1).collect();
+    tensor1(&*a).into_shape(&[m, k]).unwrap().cast_to::<TA>().unwrap().into_owned()
+}
+
+pub unsafe fn relu<K: MatMatMulKer<TI>, TA, TB, TC, TI>(
+    m: usize,
+    k: usize,
+    n: usize,
+) -> proptest::test_runner::TestCaseResult
+where
+    TA: LADatum + AsPrimitive<TI> + 'static,
+    TB: LADatum + AsPrimitive<TI> + 'static,
+    TC: LADatum + AsPrimitive<TI> + 'static,
+    TI: LADatum + AsPrimitive<TC> + 'static + Neg<Output = TI>,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    let zero = TI::zero();
+    let a = signed_a::<TA>(m, k);
+    fused_op_with_a::<K, TA, TB, TC, TI, _>(&a, n, &[FusedSpec::Max(tensor0(zero))], |exp| {
+        exp.iter_mut().for_each(|x| *x = if *x < zero { zero } else { *x })
+    })
+}
+
+pub unsafe fn clamp<K: MatMatMulKer<TI>, TA, TB, TC, TI>(
+    m: usize,
+    k: usize,
+    n: usize,
+) -> proptest::test_runner::TestCaseResult
+where
+    TA: LADatum + AsPrimitive<TI> + 'static,
+    TB: LADatum + AsPrimitive<TI> + 'static,
+    TC: LADatum + AsPrimitive<TI> + 'static,
+    TI: LADatum + AsPrimitive<TC> + 'static + Neg<Output = TI>,
+    i32: AsPrimitive<TI>,
+    usize: AsPrimitive<TI>,
+{
+    let low: TI = (-1).as_();
+    let high: TI = 1.as_();
+    let a = signed_a::<TA>(m, k);
+    let spec = [FusedSpec::Max(tensor0(low)), FusedSpec::Min(tensor0(high))];
+    fused_op_with_a::<K, TA, TB, TC, TI, _>(&a, n, &spec, |exp| {
+        exp.iter_mut().for_each(|x| {
+            *x = if *x < low {
+                low
+            } else if *x > high {
+                high
+            } else {
+                *x
+            }
+        })
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct ConvProblem<TA: LADatum, TB: LADatum> {
     pub ci: usize,
@@ -48,6 +48,30 @@ impl PseudoRightShift for f32 {
     }
 }
 
+impl PseudoRightShift for f64 {
+    fn q_even(self, mult: Self, shift: usize) -> Self {
+        self * mult * 2f64.powi(-(shift as i32))
+    }
+    fn q_to_plus_inf(self, mult: Self, shift: usize) -> Self {
+        self * mult * 2f64.powi(-(shift as i32))
+    }
+    fn q_away(self, mult: Self, shift: usize) -> Self {
+        self * mult * 2f64.powi(-(shift as i32))
+    }
+}
+
+impl PseudoRightShift for tract_data::prelude::f16 {
+    fn q_even(self, mult: Self, shift: usize) -> Self {
+        self * mult * Self::from(2f32.powi(-(shift as i32)))
+    }
+    fn q_to_plus_inf(self, mult: Self, shift: usize) -> Self {
+        self * mult * Self::from(2f32.powi(-(shift as i32)))
+    }
+    fn q_away(self, mult: Self, shift: usize) -> Self {
+        self * mult * Self::from(2f32.powi(-(shift as i32)))
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct GenericMmm4x4<TA, TB, TC, TI>(PhantomData<(TA, TB, TC, TI)>)
 where
@@ -149,241 +173,427 @@ where
     fn alignment_bytes_packed_b() -> usize {
         std::mem::size_of::<TB>()
     }
+    fn reads_strided_b() -> bool {
+        true
+    }
     #[inline(never)]
     fn kernel(spec: &MatMatMulKerSpec<TI>) -> isize {
         unsafe {
-            let mut ab = [[TI::zero(); 4]; 4];
-            match (*spec.a, *spec.b, *spec.linear) {
-                (Packed { ptr: a }, Packed { ptr: b }, Mul { k }) => {
-                    let a = a as *const TA;
-                    let b = b as *const TB;
-                    for i in 0..k {
-                        let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
-                        let b = std::slice::from_raw_parts(b.offset(4 * i as isize), 4);
-                        ab[0][0] += a[0].as_() * b[0].as_();
-                        ab[0][1] += a[0].as_() * b[1].as_();
-                        ab[0][2] += a[0].as_() * b[2].as_();
-                        ab[0][3] += a[0].as_() * b[3].as_();
-                        ab[1][0] += a[1].as_() * b[0].as_();
-                        ab[1][1] += a[1].as_() * b[1].as_();
-                        ab[1][2] += a[1].as_() * b[2].as_();
-                        ab[1][3] += a[1].as_() * b[3].as_();
-                        ab[2][0] += a[2].as_() * b[0].as_();
-                        ab[2][1] += a[2].as_() * b[1].as_();
-                        ab[2][2] += a[2].as_() * b[2].as_();
-                        ab[2][3] += a[2].as_() * b[3].as_();
-                        ab[3][0] += a[3].as_() * b[0].as_();
-                        ab[3][1] += a[3].as_() * b[1].as_();
-                        ab[3][2] += a[3].as_() * b[2].as_();
-                        ab[3][3] += a[3].as_() * b[3].as_();
-                    }
+            kernel_4x4::<TA, TB, TC, TI, _, _>(
+                spec,
+                |acc, v| {
+                    *acc += v;
+                    true
+                },
+                |acc, v| {
+                    *acc *= v;
+                    true
+                },
+            )
+        }
+    }
+}
+
+/// Body of the generic 4x4 kernels. `add` accumulates a product, an initial C value or a fused
+/// addition, `mul` applies a fused multiplication, and both return false on overflow. The
+/// products of A and B items in the main loop are not checked.
+#[inline(always)]
+unsafe fn kernel_4x4<TA, TB, TC, TI, F, G>(spec: &MatMatMulKerSpec<TI>, add: F, mul: G) -> isize
+where
+    TA: Copy + fmt::Debug + AsPrimitive<TI>,
+    TB: Copy + fmt::Debug + AsPrimitive<TI>,
+    TC: Copy + fmt::Debug + AsPrimitive<TI> + 'static + Bounded,
+    TI: Copy
+        + ops::AddAssign
+        + ops::Mul<Output = TI>
+        + ops::MulAssign
+        + PseudoRightShift
+        + PartialOrd
+        + Zero
+        + Signed
+        + fmt::Debug
+        + fmt::Display
+        + AsPrimitive<TC>
+        + 'static,
+    usize: AsPrimitive<TI>,
+    F: Fn(&mut TI, TI) -> bool,
+    G: Fn(&mut TI, TI) -> bool,
+{
+    let mut ab = [[TI::zero(); 4]; 4];
+    let mut overflow = false;
+    match (*spec.a, *spec.b, *spec.linear) {
+        (Packed { ptr: a }, Packed { ptr: b }, Mul { k }) => {
+            let a = a as *const TA;
+            let b = b as *const TB;
+            for i in 0..k {
+                let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
+                let b = std::slice::from_raw_parts(b.offset(4 * i as isize), 4);
+                overflow |= !add(&mut ab[0][0], a[0].as_() * b[0].as_());
+                overflow |= !add(&mut ab[0][1], a[0].as_() * b[1].as_());
+                overflow |= !add(&mut ab[0][2], a[0].as_() * b[2].as_());
+                overflow |= !add(&mut ab[0][3], a[0].as_() * b[3].as_());
+                overflow |= !add(&mut ab[1][0], a[1].as_() * b[0].as_());
+                overflow |= !add(&mut ab[1][1], a[1].as_() * b[1].as_());
+                overflow |= !add(&mut ab[1][2], a[1].as_() * b[2].as_());
+                overflow |= !add(&mut ab[1][3], a[1].as_() * b[3].as_());
+                overflow |= !add(&mut ab[2][0], a[2].as_() * b[0].as_());
+                overflow |= !add(&mut ab[2][1], a[2].as_() * b[1].as_());
+                overflow |= !add(&mut ab[2][2], a[2].as_() * b[2].as_());
+                overflow |= !add(&mut ab[2][3], a[2].as_() * b[3].as_());
+                overflow |= !add(&mut ab[3][0], a[3].as_() * b[0].as_());
+                overflow |= !add(&mut ab[3][1], a[3].as_() * b[1].as_());
+                overflow |= !add(&mut ab[3][2], a[3].as_() * b[2].as_());
+                overflow |= !add(&mut ab[3][3], a[3].as_() * b[3].as_());
+            }
+        }
+        (Packed { ptr: a }, OffsetsAndPtrs { row_byte_offsets, col_ptrs }, Mul { k }) => {
+            let a = a as *const TA;
+            let col_ptrs = col_ptrs as *const *const TB;
+            let pb0 = *(col_ptrs.offset(0));
+            let pb1 = *(col_ptrs.offset(1));
+            let pb2 = *(col_ptrs.offset(2));
+            let pb3 = *(col_ptrs.offset(3));
+            for i in 0..k {
+                let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
+                let offset =
+                    *row_byte_offsets.offset(i as isize) / std::mem::size_of::<TB>() as isize;
+                let b0 = *(pb0.offset(offset));
+                let b1 = *(pb1.offset(offset));
+                let b2 = *(pb2.offset(offset));
+                let b3 = *(pb3.offset(offset));
+                overflow |= !add(&mut ab[0][0], a[0].as_() * b0.as_());
+                overflow |= !add(&mut ab[0][1], a[0].as_() * b1.as_());
+                overflow |= !add(&mut ab[0][2], a[0].as_() * b2.as_());
+                overflow |= !add(&mut ab[0][3], a[0].as_() * b3.as_());
+                overflow |= !add(&mut ab[1][0], a[1].as_() * b0.as_());
+                overflow |= !add(&mut ab[1][1], a[1].as_() * b1.as_());
+                overflow |= !add(&mut ab[1][2], a[1].as_() * b2.as_());
+                overflow |= !add(&mut ab[1][3], a[1].as_() * b3.as_());
+                overflow |= !add(&mut ab[2][0], a[2].as_() * b0.as_());
+                overflow |= !add(&mut ab[2][1], a[2].as_() * b1.as_());
+                overflow |= !add(&mut ab[2][2], a[2].as_() * b2.as_());
+                overflow |= !add(&mut ab[2][3], a[2].as_() * b3.as_());
+                overflow |= !add(&mut ab[3][0], a[3].as_() * b0.as_());
+                overflow |= !add(&mut ab[3][1], a[3].as_() * b1.as_());
+                overflow |= !add(&mut ab[3][2], a[3].as_() * b2.as_());
+                overflow |= !add(&mut ab[3][3], a[3].as_() * b3.as_());
+            }
+        }
+        (
+            Packed { ptr: a },
+            Strides { ptr: b, row_byte_stride, col_byte_stride, .. },
+            Mul { k },
+        ) => {
+            let a = a as *const TA;
+            let b = b as *const TB;
+            let rsb = row_byte_stride / std::mem::size_of::<TB>() as isize;
+            let csb = col_byte_stride / std::mem::size_of::<TB>() as isize;
+            for i in 0..k {
+                let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
+                let b0 = *b.offset(rsb * i as isize);
+                let b1 = *b.offset(rsb * i as isize + csb);
+                let b2 = *b.offset(rsb * i as isize + 2 * csb);
+                let b3 = *b.offset(rsb * i as isize + 3 * csb);
+                overflow |= !add(&mut ab[0][0], a[0].as_() * b0.as_());
+                overflow |= !add(&mut ab[0][1], a[0].as_() * b1.as_());
+                overflow |= !add(&mut ab[0][2], a[0].as_() * b2.as_());
+                overflow |= !add(&mut ab[0][3], a[0].as_() * b3.as_());
+                overflow |= !add(&mut ab[1][0], a[1].as_() * b0.as_());
+                overflow |= !add(&mut ab[1][1], a[1].as_() * b1.as_());
+                overflow |= !add(&mut ab[1][2], a[1].as_() * b2.as_());
+                overflow |= !add(&mut ab[1][3], a[1].as_() * b3.as_());
+                overflow |= !add(&mut ab[2][0], a[2].as_() * b0.as_());
+                overflow |= !add(&mut ab[2][1], a[2].as_() * b1.as_());
+                overflow |= !add(&mut ab[2][2], a[2].as_() * b2.as_());
+                overflow |= !add(&mut ab[2][3], a[2].as_() * b3.as_());
+                overflow |= !add(&mut ab[3][0], a[3].as_() * b0.as_());
+                overflow |= !add(&mut ab[3][1], a[3].as_() * b1.as_());
+                overflow |= !add(&mut ab[3][2], a[3].as_() * b2.as_());
+                overflow |= !add(&mut ab[3][3], a[3].as_() * b3.as_());
+            }
+        }
+        (Packed { ptr: a }, VecStride { ptr: b, byte_stride, .. }, Mul { k }) => {
+            let a = a as *const TA;
+            let b = b as *const TB;
+            for i in 0..k {
+                let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
+                let b = *b.offset(i as isize * byte_stride / std::mem::size_of::<TB>() as isize);
+                overflow |= !add(&mut ab[0][0], a[0].as_() * b.as_());
+                overflow |= !add(&mut ab[1][0], a[1].as_() * b.as_());
+                overflow |= !add(&mut ab[2][0], a[2].as_() * b.as_());
+                overflow |= !add(&mut ab[3][0], a[3].as_() * b.as_());
+            }
+        }
+        _ => return 1,
+    }
+    let mut pnl = spec.non_linear;
+    loop {
+        if pnl.is_null() {
+            break;
+        }
+        match *pnl {
+            FusedKerSpec::Done => break,
+            FusedKerSpec::AddC => match *spec.c {
+                Strides { ptr: c, row_byte_stride, col_byte_stride, .. } => {
+                    let c = c as *const TC;
+                    let rsc = row_byte_stride as usize / std::mem::size_of::<TC>();
+                    let csc = col_byte_stride as usize / std::mem::size_of::<TC>();
+                    let c = std::slice::from_raw_parts(c, 1 + 3 * csc + 3 * rsc);
+                    overflow |= !add(&mut ab[0][0], c[0 * csc + 0 * rsc].as_());
+                    overflow |= !add(&mut ab[0][1], c[1 * csc + 0 * rsc].as_());
+                    overflow |= !add(&mut ab[0][2], c[2 * csc + 0 * rsc].as_());
+                    overflow |= !add(&mut ab[0][3], c[3 * csc + 0 * rsc].as_());
+                    overflow |= !add(&mut ab[1][0], c[0 * csc + 1 * rsc].as_());
+                    overflow |= !add(&mut ab[1][1], c[1 * csc + 1 * rsc].as_());
+                    overflow |= !add(&mut ab[1][2], c[2 * csc + 1 * rsc].as_());
+                    overflow |= !add(&mut ab[1][3], c[3 * csc + 1 * rsc].as_());
+                    overflow |= !add(&mut ab[2][0], c[0 * csc + 2 * rsc].as_());
+                    overflow |= !add(&mut ab[2][1], c[1 * csc + 2 * rsc].as_());
+                    overflow |= !add(&mut ab[2][2], c[2 * csc + 2 * rsc].as_());
+                    overflow |= !add(&mut ab[2][3], c[3 * csc + 2 * rsc].as_());
+                    overflow |= !add(&mut ab[3][0], c[0 * csc + 3 * rsc].as_());
+                    overflow |= !add(&mut ab[3][1], c[1 * csc + 3 * rsc].as_());
+                    overflow |= !add(&mut ab[3][2], c[2 * csc + 3 * rsc].as_());
+                    overflow |= !add(&mut ab[3][3], c[3 * csc + 3 * rsc].as_());
                 }
-                (Packed { ptr: a }, OffsetsAndPtrs { row_byte_offsets, col_ptrs }, Mul { k }) => {
-                    let a = a as *const TA;
-                    let col_ptrs = col_ptrs as *const *const TB;
-                    let pb0 = *(col_ptrs.offset(0));
-                    let pb1 = *(col_ptrs.offset(1));
-                    let pb2 = *(col_ptrs.offset(2));
-                    let pb3 = *(col_ptrs.offset(3));
-                    for i in 0..k {
-                        let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
-                        let offset = *row_byte_offsets.offset(i as isize)
-                            / std::mem::size_of::<TB>() as isize;
-                        let b0 = *(pb0.offset(offset));
-                        let b1 = *(pb1.offset(offset));
-                        let b2 = *(pb2.offset(offset));
-                        let b3 = *(pb3.offset(offset));
-                        ab[0][0] += a[0].as_() * b0.as_();
-                        ab[0][1] += a[0].as_() * b1.as_();
-                        ab[0][2] += a[0].as_() * b2.as_();
-                        ab[0][3] += a[0].as_() * b3.as_();
-                        ab[1][0] += a[1].as_() * b0.as_();
-                        ab[1][1] += a[1].as_() * b1.as_();
-                        ab[1][2] += a[1].as_() * b2.as_();
-                        ab[1][3] += a[1].as_() * b3.as_();
-                        ab[2][0] += a[2].as_() * b0.as_();
-                        ab[2][1] += a[2].as_() * b1.as_();
-                        ab[2][2] += a[2].as_() * b2.as_();
-                        ab[2][3] += a[2].as_() * b3.as_();
-                        ab[3][0] += a[3].as_() * b0.as_();
-                        ab[3][1] += a[3].as_() * b1.as_();
-                        ab[3][2] += a[3].as_() * b2.as_();
-                        ab[3][3] += a[3].as_() * b3.as_();
-                    }
+                _ => return 1,
+            },
+            FusedKerSpec::PerRowMul(bias) => {
+                for i in 0..4 {
+                    overflow |= !mul(&mut ab[i][0], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[i][1], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[i][2], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[i][3], *bias.offset(i as isize));
                 }
-                (Packed { ptr: a }, VecStride { ptr: b, byte_stride, .. }, Mul { k }) => {
-                    let a = a as *const TA;
-                    let b = b as *const TB;
-                    for i in 0..k {
-                        let a = std::slice::from_raw_parts(a.offset(4 * i as isize), 4);
-                        let b = *b
-                            .offset(i as isize * byte_stride / std::mem::size_of::<TB>() as isize);
-                        ab[0][0] += a[0].as_() * b.as_();
-                        ab[1][0] += a[1].as_() * b.as_();
-                        ab[2][0] += a[2].as_() * b.as_();
-                        ab[3][0] += a[3].as_() * b.as_();
-                    }
+            }
+            FusedKerSpec::PerRowAdd(bias) => {
+                for i in 0..4 {
+                    overflow |= !add(&mut ab[i][0], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[i][1], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[i][2], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[i][3], *bias.offset(i as isize));
                 }
-                _ => return 1,
             }
-            let mut pnl = spec.non_linear;
-            loop {
-                if pnl.is_null() {
-                    break;
+            FusedKerSpec::PerColMul(bias) => {
+                for i in 0..4 {
+                    overflow |= !mul(&mut ab[0][i], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[1][i], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[2][i], *bias.offset(i as isize));
+                    overflow |= !mul(&mut ab[3][i], *bias.offset(i as isize));
                 }
-                match *pnl {
-                    FusedKerSpec::Done => break,
-                    FusedKerSpec::AddC => match *spec.c {
-                        Strides { ptr: c, row_byte_stride, col_byte_stride, .. } => {
-                            let c = c as *const TC;
-                            let rsc = row_byte_stride as usize / std::mem::size_of::<TC>();
-                            let csc = col_byte_stride as usize / std::mem::size_of::<TC>();
-                            let c = std::slice::from_raw_parts(c, 1 + 3 * csc + 3 * rsc);
-                            ab[0][0] += c[0 * csc + 0 * rsc].as_();
-                            ab[0][1] += c[1 * csc + 0 * rsc].as_();
-                            ab[0][2] += c[2 * csc + 0 * rsc].as_();
-                            ab[0][3] += c[3 * csc + 0 * rsc].as_();
-                            ab[1][0] += c[0 * csc + 1 * rsc].as_();
-                            ab[1][1] += c[1 * csc + 1 * rsc].as_();
-                            ab[1][2] += c[2 * csc + 1 * rsc].as_();
-                            ab[1][3] += c[3 * csc + 1 * rsc].as_();
-                            ab[2][0] += c[0 * csc + 2 * rsc].as_();
-                            ab[2][1] += c[1 * csc + 2 * rsc].as_();
-                            ab[2][2] += c[2 * csc + 2 * rsc].as_();
-                            ab[2][3] += c[3 * csc + 2 * rsc].as_();
-                            ab[3][0] += c[0 * csc + 3 * rsc].as_();
-                            ab[3][1] += c[1 * csc + 3 * rsc].as_();
-                            ab[3][2] += c[2 * csc + 3 * rsc].as_();
-                            ab[3][3] += c[3 * csc + 3 * rsc].as_();
-                        }
-                        _ => return 1,
-                    },
-                    FusedKerSpec::PerRowMul(bias) => {
-                        for i in 0..4 {
-                            ab[i][0] *= *bias.offset(i as isize);
-                            ab[i][1] *= *bias.offset(i as isize);
-                            ab[i][2] *= *bias.offset(i as isize);
-                            ab[i][3] *= *bias.offset(i as isize);
-                        }
-                    }
-                    FusedKerSpec::PerRowAdd(bias) => {
-                        for i in 0..4 {
-                            ab[i][0] += *bias.offset(i as isize);
-                            ab[i][1] += *bias.offset(i as isize);
-                            ab[i][2] += *bias.offset(i as isize);
-                            ab[i][3] += *bias.offset(i as isize);
-                        }
-                    }
-                    FusedKerSpec::PerColMul(bias) => {
-                        for i in 0..4 {
-                            ab[0][i] *= *bias.offset(i as isize);
-                            ab[1][i] *= *bias.offset(i as isize);
-                            ab[2][i] *= *bias.offset(i as isize);
-                            ab[3][i] *= *bias.offset(i as isize);
-                        }
-                    }
-                    FusedKerSpec::PerColAdd(bias) => {
-                        for i in 0..4 {
-                            ab[0][i] += *bias.offset(i as isize);
-                            ab[1][i] += *bias.offset(i as isize);
-                            ab[2][i] += *bias.offset(i as isize);
-                            ab[3][i] += *bias.offset(i as isize);
-                        }
-                    }
-                    FusedKerSpec::Min(m) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] = if m < ab[i][j] { m } else { ab[i][j] }
-                            }
-                        }
-                    }
-                    FusedKerSpec::Max(m) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] = if m > ab[i][j] { m } else { ab[i][j] }
-                            }
-                        }
-                    }
-                    FusedKerSpec::AddRowColProducts(rows, cols) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] += *rows.offset(i as isize) * *cols.offset(j as isize);
-                            }
-                        }
+            }
+            FusedKerSpec::PerColAdd(bias) => {
+                for i in 0..4 {
+                    overflow |= !add(&mut ab[0][i], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[1][i], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[2][i], *bias.offset(i as isize));
+                    overflow |= !add(&mut ab[3][i], *bias.offset(i as isize));
+                }
+            }
+            FusedKerSpec::Min(m) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ab[i][j] = if m < ab[i][j] { m } else { ab[i][j] }
                     }
-                    FusedKerSpec::ScalarAdd(a) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] += a;
-                            }
-                        }
+                }
+            }
+            FusedKerSpec::Max(m) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ab[i][j] = if m > ab[i][j] { m } else { ab[i][j] }
                     }
-                    FusedKerSpec::ScalarMul(a) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] *= a;
-                            }
-                        }
+                }
+            }
+            FusedKerSpec::AddRowColProducts(rows, cols) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        let mut product = *rows.offset(i as isize);
+                        overflow |= !mul(&mut product, *cols.offset(j as isize));
+                        overflow |= !add(&mut ab[i][j], product);
                     }
-                    FusedKerSpec::QTowardsEven(mult, shift) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] = ab[i][j].q_even(mult, shift);
-                            }
-                        }
+                }
+            }
+            FusedKerSpec::ScalarAdd(a) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        overflow |= !add(&mut ab[i][j], a);
                     }
-                    FusedKerSpec::QTowardsPlusInf(mult, shift) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] = ab[i][j].q_to_plus_inf(mult, shift);
-                            }
-                        }
+                }
+            }
+            FusedKerSpec::ScalarMul(a) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        overflow |= !mul(&mut ab[i][j], a);
                     }
-                    FusedKerSpec::QAway(mult, shift) => {
-                        for i in 0..4 {
-                            for j in 0..4 {
-                                ab[i][j] = ab[i][j].q_away(mult, shift);
-                            }
-                        }
+                }
+            }
+            FusedKerSpec::QTowardsEven(mult, shift) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ab[i][j] = ab[i][j].q_even(mult, shift);
                     }
                 }
-                pnl = pnl.add(1);
             }
-            match *spec.c {
-                Strides { ptr: c, row_byte_stride, col_byte_stride, .. } => {
-                    let rsc = row_byte_stride as usize / std::mem::size_of::<TC>();
-                    let csc = col_byte_stride as usize / std::mem::size_of::<TC>();
-                    let c = c as *mut TC;
-                    let c = std::slice::from_raw_parts_mut(c, 1 + 3 * csc + 3 * rsc);
-                    c[0 * csc + 0 * rsc] = ab[0][0].as_();
-                    c[1 * csc + 0 * rsc] = ab[0][1].as_();
-                    c[2 * csc + 0 * rsc] = ab[0][2].as_();
-                    c[3 * csc + 0 * rsc] = ab[0][3].as_();
-                    c[0 * csc + 1 * rsc] = ab[1][0].as_();
-                    c[1 * csc + 1 * rsc] = ab[1][1].as_();
-                    c[2 * csc + 1 * rsc] = ab[1][2].as_();
-                    c[3 * csc + 1 * rsc] = ab[1][3].as_();
-                    c[0 * csc + 2 * rsc] = ab[2][0].as_();
-                    c[1 * csc + 2 * rsc] = ab[2][1].as_();
-                    c[2 * csc + 2 * rsc] = ab[2][2].as_();
-                    c[3 * csc + 2 * rsc] = ab[2][3].as_();
-                    c[0 * csc + 3 * rsc] = ab[3][0].as_();
-                    c[1 * csc + 3 * rsc] = ab[3][1].as_();
-                    c[2 * csc + 3 * rsc] = ab[3][2].as_();
-                    c[3 * csc + 3 * rsc] = ab[3][3].as_();
+            FusedKerSpec::QTowardsPlusInf(mult, shift) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ab[i][j] = ab[i][j].q_to_plus_inf(mult, shift);
+                    }
                 }
-                VecStride { ptr: c, byte_stride, .. } => {
-                    let stride = byte_stride / std::mem::size_of::<TC>() as isize;
-                    let c: *mut TC = c as _;
-                    *c.offset(0 * stride) = ab[0][0].as_();
-                    *c.offset(1 * stride) = ab[1][0].as_();
-                    *c.offset(2 * stride) = ab[2][0].as_();
-                    *c.offset(3 * stride) = ab[3][0].as_();
+            }
+            FusedKerSpec::QAway(mult, shift) => {
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ab[i][j] = ab[i][j].q_away(mult, shift);
+                    }
                 }
-                _ => return 1,
             }
         }
-        return 0;
+        pnl = pnl.add(1);
+    }
+    match *spec.c {
+        Strides { ptr: c, row_byte_stride, col_byte_stride, .. } => {
+            let rsc = row_byte_stride as usize / std::mem::size_of::<TC>();
+            let csc = col_byte_stride as usize / std::mem::size_of::<TC>();
+            let c = c as *mut TC;
+            let c = std::slice::from_raw_parts_mut(c, 1 + 3 * csc + 3 * rsc);
+            c[0 * csc + 0 * rsc] = ab[0][0].as_();
+            c[1 * csc + 0 * rsc] = ab[0][1].as_();
+            c[2 * csc + 0 * rsc] = ab[0][2].as_();
+            c[3 * csc + 0 * rsc] = ab[0][3].as_();
+            c[0 * csc + 1 * rsc] = ab[1][0].as_();
+            c[1 * csc + 1 * rsc] = ab[1][1].as_();
+            c[2 * csc + 1 * rsc] = ab[1][2].as_();
+            c[3 * csc + 1 * rsc] = ab[1][3].as_();
+            c[0 * csc + 2 * rsc] = ab[2][0].as_();
+            c[1 * csc + 2 * rsc] = ab[2][1].as_();
+            c[2 * csc + 2 * rsc] = ab[2][2].as_();
+            c[3 * csc + 2 * rsc] = ab[2][3].as_();
+            c[0 * csc + 3 * rsc] = ab[3][0].as_();
+            c[1 * csc + 3 * rsc] = ab[3][1].as_();
+            c[2 * csc + 3 * rsc] = ab[3][2].as_();
+            c[3 * csc + 3 * rsc] = ab[3][3].as_();
+        }
+        VecStride { ptr: c, byte_stride, .. } => {
+            let stride = byte_stride / std::mem::size_of::<TC>() as isize;
+            let c: *mut TC = c as _;
+            *c.offset(0 * stride) = ab[0][0].as_();
+            *c.offset(1 * stride) = ab[1][0].as_();
+            *c.offset(2 * stride) = ab[2][0].as_();
+            *c.offset(3 * stride) = ab[3][0].as_();
+        }
+        _ => return 1,
+    }
+    if overflow {
+        KERNEL_OVERFLOW
+    } else {
+        0
+    }
+}
+
+/// Accumulation policy for `GenericMmm4x4I32`, applied to the accumulation of products, the
+/// initial C value and the fused additions and multiplications (including the products of
+/// `AddRowColProducts`). The products of A and B items are not checked: they can not overflow
+/// for 8 and 16 bit inputs.
+pub trait I32Accumulator: Copy + Clone + fmt::Debug + Send + Sync + 'static {
+    /// Adds `v` to `acc`, returns false on overflow.
+    fn add(acc: &mut i32, v: i32) -> bool;
+    /// Multiplies `acc` by `v`, returns false on overflow.
+    fn mul(acc: &mut i32, v: i32) -> bool;
+}
+
+/// Detect overflows: the addition or multiplication fails.
+#[derive(Copy, Clone, Debug)]
+pub struct CheckedAdd;
+
+impl I32Accumulator for CheckedAdd {
+    #[inline(always)]
+    fn add(acc: &mut i32, v: i32) -> bool {
+        if let Some(sum) = acc.checked_add(v) {
+            *acc = sum;
+            true
+        } else {
+            false
+        }
+    }
+    #[inline(always)]
+    fn mul(acc: &mut i32, v: i32) -> bool {
+        if let Some(product) = acc.checked_mul(v) {
+            *acc = product;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Clamp the accumulators to the i32 range.
+#[derive(Copy, Clone, Debug)]
+pub struct SaturatingAdd;
+
+impl I32Accumulator for SaturatingAdd {
+    #[inline(always)]
+    fn add(acc: &mut i32, v: i32) -> bool {
+        *acc = acc.saturating_add(v);
+        true
+    }
+    #[inline(always)]
+    fn mul(acc: &mut i32, v: i32) -> bool {
+        *acc = acc.saturating_mul(v);
+        true
+    }
+}
+
+/// Generic 4x4 kernel with i32 accumulators checked for overflow, for quantized products
+/// with deep reductions. Slower than `GenericMmm4x4`, hence opt-in.
+#[derive(Copy, Clone, Debug)]
+pub struct GenericMmm4x4I32<TA, TB, TC, ACC>(PhantomData<(TA, TB, TC, ACC)>)
+where
+    TA: Copy + fmt::Debug + AsPrimitive<i32>,
+    TB: Copy + fmt::Debug + AsPrimitive<i32>,
+    TC: Copy + fmt::Debug + AsPrimitive<i32> + 'static,
+    ACC: I32Accumulator;
+
+impl<TA, TB, TC, ACC> MatMatMulKer<i32> for GenericMmm4x4I32<TA, TB, TC, ACC>
+where
+    TA: Copy + fmt::Debug + AsPrimitive<i32> + Send + Sync,
+    TB: Copy + fmt::Debug + AsPrimitive<i32> + Send + Sync,
+    TC: Copy + fmt::Debug + AsPrimitive<i32> + 'static + Bounded + Send + Sync,
+    ACC: I32Accumulator,
+    i32: AsPrimitive<TC>,
+{
+    #[inline(always)]
+    fn name() -> &'static str {
+        "generic-i32-checked"
+    }
+    #[inline(always)]
+    fn mr() -> usize {
+        4
+    }
+    #[inline(always)]
+    fn nr() -> usize {
+        4
+    }
+    fn end_padding_packed_a() -> usize {
+        0
+    }
+    fn end_padding_packed_b() -> usize {
+        0
+    }
+    #[inline(always)]
+    fn alignment_bytes_packed_a() -> usize {
+        std::mem::size_of::<TA>()
+    }
+    #[inline(always)]
+    fn alignment_bytes_packed_b() -> usize {
+        std::mem::size_of::<TB>()
+    }
+    fn reads_strided_b() -> bool {
+        true
+    }
+    #[inline(never)]
+    fn kernel(spec: &MatMatMulKerSpec<i32>) -> isize {
+        unsafe { kernel_4x4::<TA, TB, TC, i32, _, _>(spec, ACC::add, ACC::mul) }
     }
 }
 
@@ -680,6 +890,7 @@ test_mmm_kernel_i8!(crate::generic::mmm::GenericMmm4x4<i8, i8, i8, i32>, test_Ge
 test_mmm_kernel_u8!(crate::generic::mmm::GenericMmm4x4<u8, u8, u8, i32>, test_GenericMmm4x4_u8, true);
 test_mmm_kernel_i8_i32!(crate::generic::mmm::GenericMmm4x4<i8, i8, i32, i32>, test_GenericMmm4x4_i8_i32, true);
 test_mmm_kernel_i8_u8_i32!(crate::generic::mmm::GenericMmm4x4<i8, u8, i32, i32>, test_GenericMmm4x4_i8_u8_i32, true);
+test_mmm_kernel_i8_i32!(crate::generic::mmm::GenericMmm4x4I32<i8, i8, i32, crate::generic::mmm::CheckedAdd>, test_GenericMmm4x4I32_i8_i32, true);
 
 test_mmm_kernel_f32!(crate::generic::mmm::GenericMmmTest3x2<f32, f32, f32, f32>, test_GenericMmmTest3x2_f32, true);
 test_mmm_kernel_i8!(crate::generic::mmm::GenericMmmTest3x2<i8, i8, i8, i32>, test_GenericMmmTest3x2_i8, true);
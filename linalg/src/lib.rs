@@ -30,6 +30,8 @@ pub use self::frame::tanh;
 use tract_data::prelude::*;
 
 pub struct Ops {
+    /// f16 multiplier, accumulating in f32 (see `mmm_float_with_accumulator`).
+    pub mmm_f16: Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     pub mmm_f32: Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     pub qmmm_i8_i32: Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
     pub qmmm_u8_i32: Box<dyn Fn(usize, usize, usize) -> Box<dyn mmm::MatMatMul> + Send + Sync>,
@@ -54,6 +56,7 @@ impl Ops {
     ) -> Option<Box<dyn mmm::MatMatMul>> {
         use DatumType::*;
         match (a, b, c) {
+            (F16, F16, F16) => Some((self.mmm_f16)(m, k, n)),
             (F32, F32, F32) => Some((self.mmm_f32)(m, k, n)),
             (I8, I8, I32) => Some((self.qmmm_i8_i32)(m, k, n)),
             (U8, U8, I32) => Some((self.qmmm_u8_i32)(m, k, n)),
@@ -67,6 +70,15 @@ impl Ops {
 
 pub fn generic() -> Ops {
     Ops {
+        mmm_f16: Box::new(|m, k, n| {
+            Box::new(mmm::MatMatMulImpl::<
+                generic::GenericMmm4x4<f16, f16, f16, f32>,
+                f16,
+                f16,
+                f16,
+                f32,
+            >::new(m, k, n))
+        }),
         mmm_f32: Box::new(|m, k, n| {
             Box::new(mmm::MatMatMulImpl::<
                 generic::GenericMmm4x4<f32, f32, f32, f32>,
@@ -124,48 +136,184 @@ pub fn generic() -> Ops {
     }
 }
 
-#[allow(unreachable_code, unused_mut)]
+/// Best available Ops for this CPU.
+///
+/// The `TRACT_MMM_KERNEL` environment variable can force a given set of
+/// matrix multiplication kernels (see `for_kernel`), typically "generic" to rule out
+/// optimized kernels when chasing numerical differences.
+///
+/// Panics if `TRACT_MMM_KERNEL` names kernels that are not available. As
+/// `ops()` calls `best()` lazily, this happens on the first use of `ops()`.
 pub fn best() -> Ops {
+    let kernel = std::env::var("TRACT_MMM_KERNEL").ok();
+    from_env(kernel.as_deref()).unwrap_or_else(|e| panic!("TRACT_MMM_KERNEL: {}", e))
+}
+
+/// Ops for a `TRACT_MMM_KERNEL` value: the detected ones if unset.
+fn from_env(kernel: Option<&str>) -> tract_data::anyhow::Result<Ops> {
+    if let Some(kernel) = kernel {
+        for_kernel(kernel)
+    } else {
+        Ok(detected())
+    }
+}
+
+#[allow(unreachable_code, unused_mut)]
+fn detected() -> Ops {
     let mut ops = generic();
     #[cfg(target_arch = "x86_64")]
     {
         if is_x86_feature_detected!("fma") {
-            ops.mmm_f32 = Box::new(|m, k, n| {
-                Box::new(
-                    mmm::MatMatMulImpl::<x86_64_fma::mmm::MatMatMulF32x16x6, f32, f32, f32, f32>::new(
-                        m, k, n,
-                        ),
-                        )
-            });
-            log::info!("mmm_f32 x86_64/fma activated");
+            x86_64_fma::plug_fma(&mut ops);
         }
         if is_x86_feature_detected!("avx2") {
-            ops.qmmm_i8_i8 = Box::new(|m, k, n| {
-                Box::new(
-                    mmm::MatMatMulImpl::<x86_64_fma::mmm::MatMatMulI8x8x8, i8, i8, i8, i32>::new(
-                        m, k, n,
-                    ),
-                )
-            });
-            ops.qmmm_i8_i32 = Box::new(|m, k, n| {
-                Box::new(mmm::MatMatMulImpl::<
-                    x86_64_fma::mmm::MatMatMulI8xI32x8x8,
-                    i8,
-                    i8,
-                    i32,
-                    i32,
-                >::new(m, k, n))
-            });
-            log::info!("mmm_i8_i8 and mmm_i8_i32 x86_64/fma activated");
+            x86_64_fma::plug_avx2(&mut ops);
         }
     }
     #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
     arm32::plug(&mut ops);
     #[cfg(target_arch = "aarch64")]
     arm64::plug(&mut ops);
+    for plug in KERNEL_PLUGS.lock().unwrap().iter() {
+        if (plug.available)() {
+            (plug.plug)(&mut ops);
+            log::info!("registered mmm kernel {} activated", plug.name);
+        }
+    }
     return ops;
 }
 
+/// Kernels provided by another crate, plugged in `Ops` the same way as the
+/// built-in architecture specific ones.
+///
+/// `plug` typically replaces some of the `Ops` constructors with ones building
+/// a `mmm::MatMatMulImpl` over a custom `mmm::MatMatMulKer`. As the
+/// constructors get the problem size, it can pick a kernel by tile shape, and
+/// keep the previous constructor as a fallback.
+#[derive(Clone, Copy)]
+pub struct KernelPlug {
+    pub name: &'static str,
+    /// Whether the kernels can run on this CPU.
+    pub available: fn() -> bool,
+    pub plug: fn(&mut Ops),
+}
+
+lazy_static::lazy_static! {
+    static ref KERNEL_PLUGS: std::sync::Mutex<Vec<KernelPlug>> = std::sync::Mutex::new(vec![]);
+}
+
+/// Register external kernels.
+///
+/// Available registered kernels are plugged by `best()` after the built-in
+/// ones, in registration order. They can also be forced by name with
+/// `for_kernel` or `TRACT_MMM_KERNEL`. `ops()` is initialized once, so
+/// registration must happen before its first call to have an effect there.
+pub fn register_kernels(plug: KernelPlug) -> tract_data::anyhow::Result<()> {
+    let mut plugs = KERNEL_PLUGS.lock().unwrap();
+    if ["generic", "fma", "avx2", "arm64", "arm32"].contains(&plug.name)
+        || plugs.iter().any(|p| p.name == plug.name)
+    {
+        tract_data::anyhow::bail!("mmm kernel {:?} is already registered", plug.name)
+    }
+    plugs.push(plug);
+    Ok(())
+}
+
+/// Ops with the matrix multiplication kernels named by `kernel`.
+///
+/// Valid names are "generic", and depending on the platform "fma" and "avx2"
+/// (x86_64), "arm64" or "arm32", plus the names of registered kernels. Fails
+/// if the kernels are not available on this CPU.
+#[allow(unused_mut)]
+pub fn for_kernel(kernel: &str) -> tract_data::anyhow::Result<Ops> {
+    let mut ops = generic();
+    if let Some(plug) = KERNEL_PLUGS.lock().unwrap().iter().find(|p| p.name == kernel) {
+        if !(plug.available)() {
+            tract_data::anyhow::bail!("mmm kernel {:?} is not available on this CPU", kernel)
+        }
+        (plug.plug)(&mut ops);
+        return Ok(ops);
+    }
+    match kernel {
+        "generic" => (),
+        #[cfg(target_arch = "x86_64")]
+        "fma" if is_x86_feature_detected!("fma") => x86_64_fma::plug_fma(&mut ops),
+        #[cfg(target_arch = "x86_64")]
+        "avx2" if is_x86_feature_detected!("avx2") => x86_64_fma::plug_avx2(&mut ops),
+        #[cfg(target_arch = "aarch64")]
+        "arm64" => arm64::plug(&mut ops),
+        #[cfg(any(target_arch = "arm", target_arch = "armv7"))]
+        "arm32" => arm32::plug(&mut ops),
+        _ => tract_data::anyhow::bail!("mmm kernel {:?} is not available on this CPU", kernel),
+    }
+    Ok(ops)
+}
+
+/// Generic i8 x i8 -> i32 multiplier checking its i32 accumulators for overflow.
+///
+/// On overflow, `run` fails, or, if `saturate` is set, the accumulators are clamped to the i32
+/// range. This is much slower than the `Ops` multipliers and meant for validating models with
+/// deep reductions.
+pub fn qmmm_i8_i32_checked(
+    m: usize,
+    k: usize,
+    n: usize,
+    saturate: bool,
+) -> Box<dyn mmm::MatMatMul> {
+    use generic::mmm::{CheckedAdd, GenericMmm4x4I32, SaturatingAdd};
+    if saturate {
+        Box::new(
+            mmm::MatMatMulImpl::<GenericMmm4x4I32<i8, i8, i32, SaturatingAdd>, i8, i8, i32, i32>::new(
+                m, k, n,
+            ),
+        )
+    } else {
+        Box::new(
+            mmm::MatMatMulImpl::<GenericMmm4x4I32<i8, i8, i32, CheckedAdd>, i8, i8, i32, i32>::new(
+                m, k, n,
+            ),
+        )
+    }
+}
+
+/// Generic floating point multiplier accumulating in `accumulator`.
+///
+/// A, B and C are all `dt`, one of F16, F32 or F64. Without `accumulator`,
+/// f16 products are accumulated in f32, f32 and f64 ones in their own type,
+/// as with `Ops::mmm`. Accumulators are only cast back to `dt` when written to
+/// C. An accumulator can be as wide as F64, and not narrower than `dt`.
+pub fn mmm_float_with_accumulator(
+    dt: DatumType,
+    accumulator: Option<DatumType>,
+    m: usize,
+    k: usize,
+    n: usize,
+) -> tract_data::anyhow::Result<Box<dyn mmm::MatMatMul>> {
+    use generic::GenericMmm4x4;
+    use DatumType::*;
+    let accumulator = accumulator.unwrap_or(if dt == F16 { F32 } else { dt });
+    macro_rules! mmm {
+        ($t: ty, $ti: ty) => {
+            Box::new(mmm::MatMatMulImpl::<GenericMmm4x4<$t, $t, $t, $ti>, $t, $t, $t, $ti>::new(
+                m, k, n,
+            ))
+        };
+    }
+    Ok(match (dt, accumulator) {
+        (F16, F16) => mmm!(f16, f16),
+        (F16, F32) => mmm!(f16, f32),
+        (F16, F64) => mmm!(f16, f64),
+        (F32, F32) => mmm!(f32, f32),
+        (F32, F64) => mmm!(f32, f64),
+        (F64, F64) => mmm!(f64, f64),
+        _ => tract_data::anyhow::bail!(
+            "No generic {:?} multiplier accumulating in {:?}",
+            dt,
+            accumulator
+        ),
+    })
+}
+
 lazy_static::lazy_static! {
     static ref OPS: Ops = {
         best()
@@ -182,6 +330,8 @@ mod test {
     use proptest::prelude::*;
     use std::fmt::Debug;
     use std::ops::*;
+    use tract_data::internal::*;
+    use DatumType::F32;
 
     pub trait LADatum:
         Sized
@@ -252,4 +402,222 @@ mod test {
         );
         Ok(())
     }
+
+    fn mat_mul(ops: &crate::Ops, a: &Tensor, b: &Tensor) -> Tensor {
+        let (m, k, n) = (a.shape()[0], a.shape()[1], b.shape()[1]);
+        let mm = ops.mmm(F32, F32, F32, m, k, n).unwrap();
+        run_mmm::<f32, f32>(&*mm, a, b).unwrap()
+    }
+
+    fn run_mmm<TA: Datum, TC: Datum + Zero>(
+        mm: &dyn crate::mmm::MatMatMul,
+        a: &Tensor,
+        b: &Tensor,
+    ) -> tract_data::anyhow::Result<Tensor> {
+        run_mmm_fused::<TA, TC>(mm, a, b, &[])
+    }
+
+    fn run_mmm_fused<TA: Datum, TC: Datum + Zero>(
+        mm: &dyn crate::mmm::MatMatMul,
+        a: &Tensor,
+        b: &Tensor,
+        non_linear: &[crate::mmm::FusedSpec],
+    ) -> tract_data::anyhow::Result<Tensor> {
+        let (m, n) = (a.shape()[0], b.shape()[1]);
+        unsafe {
+            let mut pa = Tensor::uninitialized_aligned::<TA>(
+                &[mm.a_pack().len(m)],
+                mm.a_pack().alignment(),
+            )?;
+            mm.a_pack().pack(&mut pa.view_mut(), &a.view(), 1, 0);
+            let mut pb = Tensor::uninitialized_aligned::<TA>(
+                &[mm.b_pack().len(n)],
+                mm.b_pack().alignment(),
+            )?;
+            mm.b_pack().pack(&mut pb.view_mut(), &b.view(), 0, 1);
+            let mut c = Tensor::zero::<TC>(&[m, n])?;
+            mm.run(
+                &mm.a_packed().wrap(&pa.view()),
+                &mm.b_packed().wrap(&pb.view()),
+                &mut mm.c_view().wrap(&c.view_mut()),
+                non_linear,
+            )?;
+            Ok(c)
+        }
+    }
+
+    fn check_strided_b(ops: &crate::Ops) {
+        use crate::mmm::MatrixStoreSpec;
+        for &(m, k, n) in &[(5, 7, 8), (5, 7, 6), (3, 4, 9), (5, 3, 13), (7, 5, 14)] {
+            let a = tract_ndarray::Array2::from_shape_fn((m, k), |(i, j)| (i * k + j) as f32 / 10.);
+            let b = tract_ndarray::Array2::from_shape_fn((k, n), |(i, j)| {
+                1. - (i * n + j) as f32 / 20.
+            });
+            let expected = a.dot(&b).into_tensor();
+            let mm = ops.mmm(F32, F32, F32, m, k, n).unwrap();
+            let a = a.into_tensor();
+            let row_major = b.clone().into_tensor();
+            let col_major = b.t().to_owned().into_tensor();
+            for (b, spec) in &[
+                (row_major, MatrixStoreSpec::View { axes: None }),
+                (
+                    col_major,
+                    MatrixStoreSpec::Strides {
+                        row_byte_stride: 4,
+                        col_byte_stride: 4 * k as isize,
+                    },
+                ),
+            ] {
+                unsafe {
+                    let mut pa = Tensor::uninitialized_aligned::<f32>(
+                        &[mm.a_pack().len(m)],
+                        mm.a_pack().alignment(),
+                    )
+                    .unwrap();
+                    mm.a_pack().pack(&mut pa.view_mut(), &a.view(), 1, 0);
+                    let mut c = Tensor::zero::<f32>(&[m, n]).unwrap();
+                    mm.run(
+                        &mm.a_packed().wrap(&pa.view()),
+                        &spec.wrap(&b.view()),
+                        &mut mm.c_view().wrap(&c.view_mut()),
+                        &[],
+                    )
+                    .unwrap();
+                    c.close_enough(&expected, true).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn strided_b() {
+        check_strided_b(&crate::for_kernel("generic").unwrap());
+    }
+
+    #[test]
+    fn strided_b_with_detected_kernel() {
+        // kernels that can not read a strided B get it packed
+        check_strided_b(crate::ops());
+    }
+
+    #[test]
+    fn forced_generic_kernel_matches_detected() {
+        let a = tensor1(&(0..35).map(|i| i as f32 / 10.).collect::<Vec<_>>())
+            .into_shape(&[7, 5])
+            .unwrap();
+        let b = tensor1(&(0..45).map(|i| 1. - i as f32 / 20.).collect::<Vec<_>>())
+            .into_shape(&[5, 9])
+            .unwrap();
+        let generic = mat_mul(&crate::for_kernel("generic").unwrap(), &a, &b);
+        let detected = mat_mul(&crate::detected(), &a, &b);
+        generic.close_enough(&detected, true).unwrap();
+    }
+
+    #[test]
+    fn unknown_kernel() {
+        assert!(crate::for_kernel("no-such-kernel").is_err());
+    }
+
+    #[test]
+    fn kernel_from_env() {
+        let name = |ops: &crate::Ops| ops.mmm(F32, F32, F32, 7, 5, 9).unwrap().to_string();
+        assert!(crate::from_env(Some("no-such-kernel")).is_err());
+        assert!(name(&crate::from_env(Some("generic")).unwrap()).contains("generic"));
+        assert_eq!(name(&crate::from_env(None).unwrap()), name(&crate::detected()));
+    }
+
+    #[test]
+    fn f16_accumulates_in_f32() {
+        // long reduction: accumulating in f16 loses the small products
+        let (m, k, n) = (4, 4096, 4);
+        let a = tract_ndarray::Array2::from_shape_fn((m, k), |(i, j)| {
+            1. + ((i * 7 + j * 13) % 17) as f64 / 17.
+        });
+        let b = tract_ndarray::Array2::from_shape_fn((k, n), |(i, j)| {
+            0.01 * (1. + ((i * 5 + j * 3) % 11) as f64 / 11.)
+        });
+        let a = a.mapv(|x| -> f16 { x.as_() });
+        let b = b.mapv(|x| -> f16 { x.as_() });
+        let reference = a.mapv(|x| x.0.to_f64()).dot(&b.mapv(|x| x.0.to_f64()));
+        let (a, b) = (a.into_tensor(), b.into_tensor());
+        let error = |accumulator| {
+            let mm =
+                crate::mmm_float_with_accumulator(DatumType::F16, accumulator, m, k, n).unwrap();
+            let c = run_mmm::<f16, f16>(&*mm, &a, &b).unwrap();
+            let c = c.cast_to::<f64>().unwrap();
+            let c = c.to_array_view::<f64>().unwrap();
+            (&c - &reference.view().into_dyn()).mapv(f64::abs).fold(0f64, |a, &b| a.max(b))
+        };
+        let default = error(None);
+        assert_eq!(default, error(Some(DatumType::F32)));
+        // Ops::mmm uses the default accumulator
+        let ops = crate::generic().mmm(DatumType::F16, DatumType::F16, DatumType::F16, m, k, n);
+        let ops = ops.unwrap();
+        assert_eq!(ops.internal_type(), DatumType::F32);
+        let default_mm = crate::mmm_float_with_accumulator(DatumType::F16, None, m, k, n).unwrap();
+        assert_eq!(
+            run_mmm::<f16, f16>(&*ops, &a, &b).unwrap(),
+            run_mmm::<f16, f16>(&*default_mm, &a, &b).unwrap()
+        );
+        let in_f16 = error(Some(DatumType::F16));
+        assert!(default < reference[[0, 0]] * 1e-3, "{} {}", default, in_f16);
+        assert!(default * 10. < in_f16, "{} {}", default, in_f16);
+        assert!(crate::mmm_float_with_accumulator(DatumType::F32, Some(DatumType::F16), m, k, n)
+            .is_err());
+    }
+
+    #[test]
+    fn i32_accumulator_overflow() {
+        // 127 * 127 * 140_000 > i32::MAX
+        let (m, k, n) = (4, 140_000, 4);
+        let a = Tensor::from(tract_ndarray::Array2::from_elem((m, k), 127i8));
+        let b = Tensor::from(tract_ndarray::Array2::from_elem((k, n), 127i8));
+        if !cfg!(debug_assertions) {
+            let mm = (crate::generic().qmmm_i8_i32)(m, k, n);
+            let wrapped = run_mmm::<i8, i32>(&*mm, &a, &b).unwrap();
+            assert_eq!(wrapped.as_slice::<i32>().unwrap()[0], (127 * 127 * k) as i32);
+        }
+        let checked = crate::qmmm_i8_i32_checked(m, k, n, false);
+        assert!(run_mmm::<i8, i32>(&*checked, &a, &b).is_err());
+        let saturated = crate::qmmm_i8_i32_checked(m, k, n, true);
+        let saturated = run_mmm::<i8, i32>(&*saturated, &a, &b).unwrap();
+        assert!(saturated.as_slice::<i32>().unwrap().iter().all(|&x| x == std::i32::MAX));
+    }
+
+    #[test]
+    fn i32_accumulator_overflow_in_fused_ops() {
+        use crate::mmm::FusedSpec;
+        // 127 * 127 * 130_000 fits in i32, adding 60_000_000 or doubling it does not
+        let (m, k, n) = (4, 130_000, 4);
+        let a = Tensor::from(tract_ndarray::Array2::from_elem((m, k), 127i8));
+        let b = Tensor::from(tract_ndarray::Array2::from_elem((k, n), 127i8));
+        let product = (127 * 127 * k) as i32;
+        let adds = vec![
+            FusedSpec::ScalarAdd(tensor0(60_000_000i32)),
+            FusedSpec::PerRowAdd(tensor1(&[0, 0, 60_000_000i32, 0])),
+            FusedSpec::PerColAdd(tensor1(&[60_000_000i32, 0, 0, 0])),
+            FusedSpec::AddRowColProducts(
+                tensor1(&[6_000i32, 6_000, 6_000, 6_000]),
+                tensor1(&[10_000i32, 10_000, 10_000, 10_000]),
+            ),
+            FusedSpec::ScalarMul(tensor0(2i32)),
+            FusedSpec::PerRowMul(tensor1(&[1, 2i32, 1, 1])),
+            FusedSpec::PerColMul(tensor1(&[1, 1, 1, 2i32])),
+            // the row and column product itself overflows
+            FusedSpec::AddRowColProducts(
+                tensor1(&[0, 0, 0, 100_000i32]),
+                tensor1(&[100_000i32, 0, 0, 0]),
+            ),
+        ];
+        let checked = crate::qmmm_i8_i32_checked(m, k, n, false);
+        let c = run_mmm::<i8, i32>(&*checked, &a, &b).unwrap();
+        assert!(c.as_slice::<i32>().unwrap().iter().all(|&x| x == product));
+        let saturating = crate::qmmm_i8_i32_checked(m, k, n, true);
+        for add in adds {
+            let fused = [add.clone()];
+            assert!(run_mmm_fused::<i8, i32>(&*checked, &a, &b, &fused).is_err(), "{:?}", add);
+            let c = run_mmm_fused::<i8, i32>(&*saturating, &a, &b, &fused).unwrap();
+            assert!(c.as_slice::<i32>().unwrap().iter().any(|&x| x == std::i32::MAX), "{:?}", add);
+        }
+    }
 }
@@ -1 +1,21 @@
 pub mod mmm;
+
+use crate::frame::MatMatMulImpl;
+use crate::Ops;
+
+pub fn plug_fma(ops: &mut Ops) {
+    ops.mmm_f32 = Box::new(|m, k, n| {
+        Box::new(MatMatMulImpl::<mmm::MatMatMulF32x16x6, f32, f32, f32, f32>::new(m, k, n))
+    });
+    log::info!("mmm_f32 x86_64/fma activated");
+}
+
+pub fn plug_avx2(ops: &mut Ops) {
+    ops.qmmm_i8_i8 = Box::new(|m, k, n| {
+        Box::new(MatMatMulImpl::<mmm::MatMatMulI8x8x8, i8, i8, i8, i32>::new(m, k, n))
+    });
+    ops.qmmm_i8_i32 = Box::new(|m, k, n| {
+        Box::new(MatMatMulImpl::<mmm::MatMatMulI8xI32x8x8, i8, i8, i32, i32>::new(m, k, n))
+    });
+    log::info!("mmm_i8_i8 and mmm_i8_i32 x86_64/fma activated");
+}
@@ -0,0 +1,116 @@
+//! Registered kernels are global: this test lives in its own binary so that
+//! the naive kernel is never picked up by other tests.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tract_data::internal::*;
+use tract_linalg::mmm::*;
+
+static NAIVE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Plain rust 1x1 kernel, only supporting packed inputs and no fused ops.
+#[derive(Copy, Clone, Debug)]
+struct Naive1x1;
+
+impl MatMatMulKer<f32> for Naive1x1 {
+    fn name() -> &'static str {
+        "naive"
+    }
+    fn kernel(spec: &MatMatMulKerSpec<f32>) -> isize {
+        NAIVE_CALLS.fetch_add(1, Ordering::SeqCst);
+        unsafe {
+            let k = if let LinearSpec::Mul { k } = *spec.linear { k } else { 0 };
+            let (a, b, b_stride) = match (*spec.a, *spec.b) {
+                (PanelStore::Packed { ptr: a }, PanelStore::Packed { ptr: b }) => (a, b, 4),
+                (PanelStore::Packed { ptr: a }, PanelStore::VecStride { ptr, byte_stride, .. }) => {
+                    (a, ptr, byte_stride)
+                }
+                _ => return 1,
+            };
+            let mut acc = 0f32;
+            for i in 0..k {
+                acc += *(a as *const f32).add(i)
+                    * *((b as *const u8).offset(b_stride * i as isize) as *const f32);
+            }
+            if *spec.non_linear != FusedKerSpec::Done {
+                return 1;
+            }
+            match *spec.c {
+                PanelStore::Strides { ptr, .. } => *(ptr as *mut f32) = acc,
+                _ => return 1,
+            }
+        }
+        0
+    }
+    fn mr() -> usize {
+        1
+    }
+    fn nr() -> usize {
+        1
+    }
+    fn alignment_bytes_packed_a() -> usize {
+        4
+    }
+    fn end_padding_packed_a() -> usize {
+        0
+    }
+    fn alignment_bytes_packed_b() -> usize {
+        4
+    }
+    fn end_padding_packed_b() -> usize {
+        0
+    }
+}
+
+fn mat_mul(ops: &tract_linalg::Ops, a: &Tensor, b: &Tensor) -> Tensor {
+    let (m, k, n) = (a.shape()[0], a.shape()[1], b.shape()[1]);
+    let mm = ops.mmm(DatumType::F32, DatumType::F32, DatumType::F32, m, k, n).unwrap();
+    unsafe {
+        let mut pa =
+            Tensor::uninitialized_aligned::<f32>(&[mm.a_pack().len(m)], mm.a_pack().alignment())
+                .unwrap();
+        mm.a_pack().pack(&mut pa.view_mut(), &a.view(), 1, 0);
+        let mut pb =
+            Tensor::uninitialized_aligned::<f32>(&[mm.b_pack().len(n)], mm.b_pack().alignment())
+                .unwrap();
+        mm.b_pack().pack(&mut pb.view_mut(), &b.view(), 0, 1);
+        let mut c = Tensor::zero::<f32>(&[m, n]).unwrap();
+        mm.run(
+            &mm.a_packed().wrap(&pa.view()),
+            &mm.b_packed().wrap(&pb.view()),
+            &mut mm.c_view().wrap(&c.view_mut()),
+            &[],
+        )
+        .unwrap();
+        c
+    }
+}
+
+#[test]
+fn registered_kernel() {
+    tract_linalg::register_kernels(tract_linalg::KernelPlug {
+        name: "naive",
+        available: || true,
+        plug: |ops| {
+            ops.mmm_f32 = Box::new(|m, k, n| {
+                Box::new(MatMatMulImpl::<Naive1x1, f32, f32, f32, f32>::new(m, k, n))
+            })
+        },
+    })
+    .unwrap();
+    let a =
+        tensor1(&(0..15).map(|i| i as f32 / 10.).collect::<Vec<_>>()).into_shape(&[3, 5]).unwrap();
+    let b = tensor1(&(0..20).map(|i| 1. - i as f32 / 20.).collect::<Vec<_>>())
+        .into_shape(&[5, 4])
+        .unwrap();
+    let generic = mat_mul(&tract_linalg::for_kernel("generic").unwrap(), &a, &b);
+    assert_eq!(NAIVE_CALLS.load(Ordering::SeqCst), 0);
+    let naive = mat_mul(&tract_linalg::for_kernel("naive").unwrap(), &a, &b);
+    assert_eq!(NAIVE_CALLS.load(Ordering::SeqCst), 12);
+    naive.close_enough(&generic, true).unwrap();
+    assert!(tract_linalg::register_kernels(tract_linalg::KernelPlug {
+        name: "generic",
+        available: || true,
+        plug: |_| (),
+    })
+    .is_err());
+}
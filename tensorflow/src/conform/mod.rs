@@ -46,3 +46,7 @@ pub fn const_i32(name: &str, t: &Tensor) -> tfpb::tensorflow::NodeDef {
 pub fn placeholder_i32(name: &str) -> tfpb::tensorflow::NodeDef {
     placeholder(name, DataType::DtInt32, None)
 }
+
+pub fn placeholder_i16(name: &str) -> tfpb::tensorflow::NodeDef {
+    placeholder(name, DataType::DtInt16, None)
+}
@@ -1,5 +1,9 @@
 #![allow(dead_code)]
 
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use std::{fs, path};
 
 use tensorflow as tf;
@@ -13,10 +17,10 @@ use tract_hir::internal::*;
 use tract_ndarray::prelude::*;
 
 use std::collections::HashMap;
-use std::collections::HashSet;
 
 pub struct Tensorflow {
-    graph: Graph,
+    graph: Arc<Graph>,
+    timeout: Option<Duration>,
 }
 
 pub fn version() -> String {
@@ -31,9 +35,70 @@ pub fn for_path<P: AsRef<path::Path>>(p: P) -> TractResult<Tensorflow> {
 }
 
 pub fn for_slice(buf: &[u8]) -> TractResult<Tensorflow> {
+    Ok(Tensorflow { graph: Arc::new(import(buf)?), timeout: None })
+}
+
+fn import(buf: &[u8]) -> TractResult<Graph> {
     let mut graph = Graph::new();
     graph.import_graph_def(buf, &::tensorflow::ImportGraphDefOptions::new())?;
-    Ok(Tensorflow { graph })
+    Ok(graph)
+}
+
+/// Most recently used graphs first, keyed by canonical path and modification
+/// time.
+struct GraphCache {
+    capacity: usize,
+    graphs: VecDeque<(path::PathBuf, SystemTime, Arc<Graph>)>,
+}
+
+lazy_static::lazy_static! {
+    static ref GRAPH_CACHE: Mutex<GraphCache> =
+        Mutex::new(GraphCache { capacity: 8, graphs: VecDeque::new() });
+}
+
+static CACHE_MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of graphs `for_path_cached` had to import so far.
+pub fn graph_cache_misses() -> usize {
+    CACHE_MISSES.load(Ordering::SeqCst)
+}
+
+/// Set how many graphs `for_path_cached` keeps around. Zero disables the
+/// cache.
+pub fn set_graph_cache_capacity(capacity: usize) {
+    let mut cache = GRAPH_CACHE.lock().unwrap();
+    cache.capacity = capacity;
+    cache.graphs.truncate(capacity);
+}
+
+/// Same as `for_path`, but shares the imported graph with previous and later
+/// calls on the same file, as long as it is not modified.
+pub fn for_path_cached<P: AsRef<path::Path>>(p: P) -> TractResult<Tensorflow> {
+    let path =
+        fs::canonicalize(p.as_ref()).with_context(|| format!("Canonicalizing {:?}", p.as_ref()))?;
+    let mtime = fs::metadata(&path)?.modified()?;
+    {
+        let mut cache = GRAPH_CACHE.lock().unwrap();
+        if let Some(ix) = cache.graphs.iter().position(|(p, _, _)| p == &path) {
+            let entry = cache.graphs.remove(ix).unwrap();
+            if entry.1 == mtime {
+                let graph = entry.2.clone();
+                cache.graphs.push_front(entry);
+                return Ok(Tensorflow { graph, timeout: None });
+            }
+        }
+    }
+    // import without holding the lock, so other threads can use the cache
+    let graph = Arc::new(import(&fs::read(&path)?)?);
+    CACHE_MISSES.fetch_add(1, Ordering::SeqCst);
+    let mut cache = GRAPH_CACHE.lock().unwrap();
+    if cache.capacity > 0 {
+        cache.graphs.retain(|(p, _, _)| p != &path);
+        cache.graphs.push_front((path, mtime, graph.clone()));
+        let capacity = cache.capacity;
+        cache.graphs.truncate(capacity);
+    }
+    Ok(Tensorflow { graph, timeout: None })
 }
 
 enum TensorHolder {
@@ -51,41 +116,61 @@ enum TensorHolder {
 }
 
 impl TensorHolder {
-    fn to_tensor<T: ::tensorflow::TensorType + Copy>(m: ArrayD<T>) -> tf::Tensor<T> {
+    /// Copy the tensor content straight into a new TF tensor. libtensorflow
+    /// owns the buffers it is fed with, so this single copy is the best we can
+    /// do.
+    fn to_tensor<T: ::tensorflow::TensorType + Copy + Datum>(m: &Tensor) -> tf::Tensor<T> {
         let dims: Vec<u64> = m.shape().iter().map(|d| *d as _).collect();
         let mut tensor = tf::Tensor::<T>::new(&*dims);
-        tensor.copy_from_slice(m.as_slice().unwrap());
+        tensor.copy_from_slice(m.as_slice::<T>().unwrap());
         tensor
     }
 }
 
-impl From<Tensor> for TensorHolder {
-    fn from(m: Tensor) -> TensorHolder {
+impl<'a> From<&'a Tensor> for TensorHolder {
+    fn from(m: &'a Tensor) -> TensorHolder {
         match m.datum_type() {
-            DatumType::Bool => TensorHolder::Bool(Self::to_tensor(m.into_array().unwrap())),
+            DatumType::Bool => TensorHolder::Bool(Self::to_tensor(m)),
             DatumType::F16 => unimplemented!(),
-            DatumType::F32 => TensorHolder::F32(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::F64 => TensorHolder::F64(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::I8 => TensorHolder::I8(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::I16 => TensorHolder::I16(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::I32 => TensorHolder::I32(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::I64 => TensorHolder::I64(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::U8 => TensorHolder::U8(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::U16 => TensorHolder::U16(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::U32 => TensorHolder::U16(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::U64 => TensorHolder::U16(Self::to_tensor(m.into_array().unwrap())),
+            DatumType::F32 => TensorHolder::F32(Self::to_tensor(m)),
+            DatumType::F64 => TensorHolder::F64(Self::to_tensor(m)),
+            DatumType::I8 => TensorHolder::I8(Self::to_tensor(m)),
+            DatumType::I16 => TensorHolder::I16(Self::to_tensor(m)),
+            DatumType::I32 => TensorHolder::I32(Self::to_tensor(m)),
+            DatumType::I64 => TensorHolder::I64(Self::to_tensor(m)),
+            DatumType::U8 => TensorHolder::U8(Self::to_tensor(m)),
+            DatumType::U16 => TensorHolder::U16(Self::to_tensor(m)),
+            DatumType::U32 => TensorHolder::U16(Self::to_tensor(m)),
+            DatumType::U64 => TensorHolder::U16(Self::to_tensor(m)),
             DatumType::TDim => {
                 let dims = m.to_array_view::<TDim>().unwrap();
                 if let Ok(dims) = dims.iter().map(|d| d.to_i32()).collect::<TractResult<Vec<_>>>() {
-                    TensorHolder::I32(Self::to_tensor(arr1(&dims).into_dyn()))
+                    TensorHolder::I32(Self::to_tensor(&arr1(&dims).into_tensor()))
                 } else {
                     panic!("Streaming used in tensorflow settings")
                 }
             }
-            DatumType::String => TensorHolder::String(Self::to_tensor(m.into_array().unwrap())),
-            DatumType::Blob => TensorHolder::String(Self::to_tensor(m.into_array().unwrap())),
+            DatumType::String => TensorHolder::String(Self::to_tensor(m)),
+            DatumType::Blob => TensorHolder::String(Self::to_tensor(m)),
+        }
+    }
+}
+
+impl From<Tensor> for TensorHolder {
+    fn from(m: Tensor) -> TensorHolder {
+        (&m).into()
+    }
+}
+
+/// Split a `name:index` output reference into the operation name and the
+/// output index. Names without a numeric suffix are returned as is.
+fn split_port(name: &str) -> (&str, Option<i32>) {
+    if let Some(pos) = name.rfind(':') {
+        if let Ok(ix) = name[pos + 1..].parse::<i32>() {
+            return (&name[..pos], Some(ix));
         }
     }
+    (name, None)
 }
 
 fn tensor_to_array<T: ::tensorflow::TensorType>(tensor: &tf::Tensor<T>) -> TractResult<ArrayD<T>> {
@@ -94,90 +179,149 @@ fn tensor_to_array<T: ::tensorflow::TensorType>(tensor: &tf::Tensor<T>) -> Tract
 }
 
 impl Tensorflow {
+    /// Bound the duration of every subsequent run. TensorFlow cancels a run
+    /// exceeding it, and the executor stays usable for the next calls.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    fn session_run(&self, step: &mut SessionRunArgs) -> TractResult<()> {
+        if let Some(timeout) = self.timeout {
+            // serialized RunOptions proto, with only timeout_in_ms (field 2) set
+            let mut options = vec![];
+            prost::encoding::int64::encode(2, &(timeout.as_millis().max(1) as i64), &mut options);
+            step.set_run_options(&options);
+        }
+        let mut session = Session::new(&::tensorflow::SessionOptions::new(), &self.graph)?;
+        match session.run(step) {
+            Err(e) if e.code() == tf::Code::DeadlineExceeded => {
+                bail!("TensorFlow run timed out after {:?}", self.timeout.unwrap())
+            }
+            r => Ok(r?),
+        }
+    }
+
+    /// Check fed names are distinct and exist in the graph, so mistakes do not
+    /// end up in TensorFlow silently picking one of the values or failing with
+    /// an opaque error.
+    fn check_inputs<'n>(&self, names: impl Iterator<Item = &'n str>) -> TractResult<()> {
+        let mut seen = std::collections::HashSet::new();
+        for name in names {
+            let (op, slot) = split_port(name);
+            if !seen.insert((op, slot.unwrap_or(0))) {
+                bail!("Input {} is fed more than once", name)
+            }
+            if self.graph.operation_by_name(op)?.is_none() {
+                let mut placeholders = vec![];
+                for op in self.graph.operation_iter() {
+                    if op.op_type()? == "Placeholder" {
+                        placeholders.push(op.name()?);
+                    }
+                }
+                bail!(
+                    "Input {} is not a node of the graph. Placeholders are: {}",
+                    name,
+                    placeholders.join(", ")
+                )
+            }
+        }
+        Ok(())
+    }
+
     /// Executes the graph in one batch.
     pub fn run(
         &mut self,
         inputs: Vec<(&str, Tensor)>,
         output_name: &str,
     ) -> TractResult<Vec<Tensor>> {
+        self.run_borrowed(inputs.iter().map(|(name, t)| (*name, t)).collect(), output_name)
+    }
+
+    /// Executes the graph in one batch, without taking ownership of the
+    /// inputs. Each input is copied once, directly into TensorFlow memory.
+    pub fn run_borrowed(
+        &mut self,
+        inputs: Vec<(&str, &Tensor)>,
+        output_name: &str,
+    ) -> TractResult<Vec<Tensor>> {
+        self.check_inputs(inputs.iter().map(|(name, _)| *name))?;
         let tensors: Vec<(&str, TensorHolder)> =
             inputs.into_iter().map(|(name, mat)| (name, mat.into())).collect();
 
         let mut step = SessionRunArgs::new();
         for t in &tensors {
-            let op = self.graph.operation_by_name_required(t.0)?;
+            let (name, slot) = split_port(t.0);
+            let op = self.graph.operation_by_name_required(name)?;
+            let slot = slot.unwrap_or(0);
             match t.1 {
-                TensorHolder::Bool(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::U8(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::U16(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I8(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I16(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I32(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I64(ref it) => step.add_feed(&op, 0, &it),
+                TensorHolder::Bool(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::U8(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::U16(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I8(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I16(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I32(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I64(ref it) => step.add_feed(&op, slot, &it),
                 TensorHolder::F16(_) => unimplemented!(),
-                TensorHolder::F32(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::F64(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::String(ref it) => step.add_feed(&op, 0, &it),
+                TensorHolder::F32(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::F64(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::String(ref it) => step.add_feed(&op, slot, &it),
             }
         }
 
+        let (output_name, output_slot) = split_port(output_name);
         let op = &self.graph.operation_by_name_required(output_name)?;
+        let slots = if let Some(slot) = output_slot {
+            vec![slot as usize]
+        } else {
+            (0..op.num_outputs()).collect()
+        };
         let tokens =
-            (0..op.num_outputs()).map(|ix| step.request_fetch(&op, ix as i32)).collect::<Vec<_>>();
+            slots.iter().map(|&ix| (ix, step.request_fetch(&op, ix as i32))).collect::<Vec<_>>();
 
-        let mut session = Session::new(&::tensorflow::SessionOptions::new(), &self.graph)?;
-        session.run(&mut step)?;
+        self.session_run(&mut step)?;
 
         tokens
             .into_iter()
-            .enumerate()
-            .map(|(ix, tok)| {
-                let output_type =
-                    &self.graph.operation_by_name_required(&output_name)?.output_type(ix);
-                convert_output(&mut step, output_type, tok)
-            })
+            .map(|(ix, tok)| convert_output(&mut step, &op.output_type(ix), tok))
             .collect()
     }
 
-    /// Executes the graph in one batch, and returns the output for every node but the inputs.
+    /// Executes the graph in one batch, and returns the outputs of every target.
+    ///
+    /// Targets may include fed nodes: their fetched value is the fed one.
     pub fn run_get_many<'a>(
         &mut self,
         inputs: Vec<(&'a str, Tensor)>,
         targets: Vec<&'a str>,
     ) -> TractResult<HashMap<&'a str, Vec<Tensor>>> {
-        let mut input_pairs: Vec<(&str, TensorHolder)> = Vec::new();
-        let mut excluded = HashSet::new();
-
-        for (name, mat) in inputs {
-            input_pairs.push((name, mat.into()));
-            excluded.insert(name.to_string());
-        }
+        self.check_inputs(inputs.iter().map(|(name, _)| *name))?;
+        let input_pairs: Vec<(&str, TensorHolder)> =
+            inputs.into_iter().map(|(name, mat)| (name, mat.into())).collect();
 
         let mut step = SessionRunArgs::new();
         for t in &input_pairs {
-            let op = self.graph.operation_by_name_required(t.0)?;
+            let (name, slot) = split_port(t.0);
+            let op = self.graph.operation_by_name_required(name)?;
+            let slot = slot.unwrap_or(0);
             match t.1 {
-                TensorHolder::Bool(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::U8(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::U16(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I8(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I16(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I32(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::I64(ref it) => step.add_feed(&op, 0, &it),
+                TensorHolder::Bool(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::U8(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::U16(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I8(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I16(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I32(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::I64(ref it) => step.add_feed(&op, slot, &it),
                 TensorHolder::F16(_) => unimplemented!(),
-                TensorHolder::F32(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::F64(ref it) => step.add_feed(&op, 0, &it),
-                TensorHolder::String(ref it) => step.add_feed(&op, 0, &it),
+                TensorHolder::F32(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::F64(ref it) => step.add_feed(&op, slot, &it),
+                TensorHolder::String(ref it) => step.add_feed(&op, slot, &it),
             }
         }
 
         let mut tokens = HashMap::new();
         trace!("Targets: {:?}", targets);
-        for name in targets {
-            if excluded.contains(name) {
-                continue;
-            }
-
+        for target in targets {
+            let (name, slot) = split_port(target);
             if let Some(operation) = self.graph.operation_by_name(name)? {
                 // switch only computes one of its outputs. tf explodes during
                 // the call to run() if we registers them
@@ -190,32 +334,32 @@ impl Tensorflow {
                     continue;
                 }
 
-                let outputs = (0..operation.num_outputs())
-                    .map(|ix| step.request_fetch(&operation, ix as i32))
+                let slots = if let Some(slot) = slot {
+                    vec![slot as usize]
+                } else {
+                    (0..operation.num_outputs()).collect()
+                };
+                let outputs = slots
+                    .into_iter()
+                    .map(|ix| (ix, step.request_fetch(&operation, ix as i32)))
                     .collect::<Vec<_>>();
 
-                tokens.insert(name, outputs);
+                tokens.insert(target, (operation, outputs));
             }
         }
         trace!("Generated all output tokens");
         trace!("{:?}", tokens);
 
         // Execute the graph using tensorflow.
-        let mut session = Session::new(&::tensorflow::SessionOptions::new(), &self.graph)?;
-        session.run(&mut step)?;
+        self.session_run(&mut step)?;
         trace!("Tensorflow ran succesfully");
 
         // Return the output for every node.
         let mut outputs = HashMap::new();
-        for (name, tokens) in tokens {
+        for (name, (operation, tokens)) in tokens {
             let tensors = tokens
                 .iter()
-                .enumerate()
-                .map(|(ix, tok)| {
-                    let output_type =
-                        &self.graph.operation_by_name_required(&name)?.output_type(ix);
-                    convert_output(&mut step, output_type, *tok)
-                })
+                .map(|(ix, tok)| convert_output(&mut step, &operation.output_type(*ix), *tok))
                 .collect::<TractResult<Vec<_>>>()?;
             outputs.insert(name, tensors);
         }
@@ -244,7 +388,9 @@ fn convert_output(
         DataType::Float => convert!(f32),
         DataType::Double => convert!(f64),
         DataType::UInt8 => convert!(u8),
+        DataType::UInt16 => convert!(u16),
         DataType::Int8 => convert!(i8),
+        DataType::Int16 => convert!(i16),
         DataType::Int32 => convert!(i32),
         DataType::Int64 => convert!(i64),
         t => bail!("Missing conversion for tensorflow to tract (type: {:?})", t),
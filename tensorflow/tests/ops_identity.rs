@@ -0,0 +1,197 @@
+#![cfg(feature = "conform")]
+#![allow(non_snake_case)]
+extern crate env_logger;
+#[macro_use]
+extern crate log;
+extern crate proptest;
+extern crate tract_tensorflow;
+
+mod utils;
+
+use crate::utils::*;
+use tract_tensorflow::conform::*;
+use tract_tensorflow::prelude::*;
+use tract_tensorflow::tfpb;
+use tract_tensorflow::tfpb::tensorflow::DataType;
+
+#[test]
+fn identity_i16_waveform() {
+    let samples: Vec<i16> = (0..1600)
+        .map(|i| ((i as f32 / 8.0).sin() * std::i16::MAX as f32) as i16)
+        .chain(vec![std::i16::MIN, std::i16::MAX, 0, -1])
+        .collect();
+    let input = tensor1(&samples);
+    let graph = tfpb::graph()
+        .node(placeholder_i16("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtInt16));
+    let graph = graph.write_to_bytes().unwrap();
+    let found = tract_tensorflow::conform::tf::for_slice(&graph)
+        .unwrap()
+        .run(vec![("input", input.clone())], "op")
+        .unwrap();
+    assert_eq!(found[0], input);
+    compare(&graph, vec![("input", input)], "op").unwrap();
+}
+
+#[test]
+fn run_borrowed_matches_run() {
+    let input: Tensor =
+        tract_ndarray::Array::from_shape_fn((16, 256, 256), |(a, b, c)| (a * b + c) as f32 / 7.0)
+            .into();
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtFloat));
+    let graph = graph.write_to_bytes().unwrap();
+    let mut tf = tract_tensorflow::conform::tf::for_slice(&graph).unwrap();
+    let borrowed = tf.run_borrowed(vec![("input", &input)], "op").unwrap();
+    let owned = tf.run(vec![("input", input.clone())], "op").unwrap();
+    assert_eq!(borrowed, owned);
+    assert_eq!(borrowed[0], input);
+}
+
+#[test]
+fn fetch_fed_placeholder() {
+    let input = tensor1(&[1f32, 2., 3.]);
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtFloat));
+    let graph = graph.write_to_bytes().unwrap();
+    let mut tf = tract_tensorflow::conform::tf::for_slice(&graph).unwrap();
+    let found = tf.run(vec![("input", input.clone())], "input").unwrap();
+    assert_eq!(found, vec![input.clone()]);
+    let found = tf.run_get_many(vec![("input", input.clone())], vec!["input", "op"]).unwrap();
+    assert_eq!(found["input"], vec![input.clone()]);
+    assert_eq!(found["op"], vec![input]);
+}
+
+#[test]
+fn run_rejects_bad_feeds() {
+    let input = tensor1(&[1f32, 2., 3.]);
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtFloat));
+    let graph = graph.write_to_bytes().unwrap();
+    let mut tf = tract_tensorflow::conform::tf::for_slice(&graph).unwrap();
+    let err = tf.run(vec![("input", input.clone()), ("input", input.clone())], "op").unwrap_err();
+    assert_eq!(err.to_string(), "Input input is fed more than once");
+    let err = tf.run(vec![("inptu", input)], "op").unwrap_err();
+    assert_eq!(err.to_string(), "Input inptu is not a node of the graph. Placeholders are: input");
+}
+
+#[test]
+fn output_index_suffix() {
+    let input = tensor1(&[1f32, 2., 3.]);
+    let graph = tfpb::graph().node(placeholder_f32("input")).node(
+        tfpb::node().name("logits").op("Identity").input("input").attr("T", DataType::DtFloat),
+    );
+    let graph = graph.write_to_bytes().unwrap();
+    let mut tf = tract_tensorflow::conform::tf::for_slice(&graph).unwrap();
+    let bare = tf.run(vec![("input", input.clone())], "logits").unwrap();
+    let suffixed = tf.run(vec![("input:0", input.clone())], "logits:0").unwrap();
+    assert_eq!(bare, suffixed);
+    let found =
+        tf.run_get_many(vec![("input:0", input.clone())], vec!["logits", "logits:0"]).unwrap();
+    assert_eq!(found["logits"], found["logits:0"]);
+    let err = tf.run(vec![("input", input.clone()), ("input:0", input)], "logits").unwrap_err();
+    assert_eq!(err.to_string(), "Input input:0 is fed more than once");
+}
+
+lazy_static::lazy_static! {
+    /// Serializes the tests using the process-wide graph cache and its miss counter.
+    static ref GRAPH_CACHE_TESTS: std::sync::Mutex<()> = std::sync::Mutex::new(());
+}
+
+/// A temporary graph file path, unique to this process.
+fn temp_graph_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("tract-tf-{}-{}.pb", name, std::process::id()))
+}
+
+#[test]
+fn cached_graph_is_imported_once() {
+    let _lock = GRAPH_CACHE_TESTS.lock().unwrap();
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtFloat));
+    let path = temp_graph_path("cached-identity");
+    std::fs::write(&path, graph.write_to_bytes().unwrap()).unwrap();
+    let input = tensor1(&[1f32, 2., 3.]);
+    let misses = tf::graph_cache_misses();
+    let mut first = tf::for_path_cached(&path).unwrap();
+    assert_eq!(tf::graph_cache_misses(), misses + 1);
+    let mut second = tf::for_path_cached(&path).unwrap();
+    assert_eq!(tf::graph_cache_misses(), misses + 1);
+    assert_eq!(
+        first.run(vec![("input", input.clone())], "op").unwrap(),
+        second.run(vec![("input", input)], "op").unwrap()
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn cached_graph_is_invalidated_on_rewrite() {
+    let _lock = GRAPH_CACHE_TESTS.lock().unwrap();
+    let graph = |op: &str| {
+        tfpb::graph()
+            .node(placeholder_f32("input"))
+            .node(tfpb::node().name("op").op(op).input("input").attr("T", DataType::DtFloat))
+            .write_to_bytes()
+            .unwrap()
+    };
+    let path = temp_graph_path("cached-rewrite");
+    std::fs::write(&path, graph("Identity")).unwrap();
+    let input = tensor1(&[1f32, 2., 3.]);
+    let run =
+        || tf::for_path_cached(&path).unwrap().run(vec![("input", input.clone())], "op").unwrap();
+    assert_eq!(run(), vec![tensor1(&[1f32, 2., 3.])]);
+    let misses = tf::graph_cache_misses();
+    assert_eq!(run(), vec![tensor1(&[1f32, 2., 3.])]);
+    assert_eq!(tf::graph_cache_misses(), misses);
+    // rewrite until the mtime changes, file systems may have a coarse resolution
+    let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+    while std::fs::metadata(&path).unwrap().modified().unwrap() == mtime {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        std::fs::write(&path, graph("Neg")).unwrap();
+    }
+    assert_eq!(run(), vec![tensor1(&[-1f32, -2., -3.])]);
+    assert_eq!(tf::graph_cache_misses(), misses + 1);
+    assert_eq!(run(), vec![tensor1(&[-1f32, -2., -3.])]);
+    assert_eq!(tf::graph_cache_misses(), misses + 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn run_timeout() {
+    use tfpb::tensorflow::attr_value::{ListValue, Value};
+    use tfpb::tensorflow::AttrValue;
+    let input = tensor1(&[1f32, 2., 3.]);
+    let float_list = AttrValue {
+        value: Some(Value::List(ListValue {
+            r#type: vec![DataType::DtFloat as i32],
+            ..ListValue::default()
+        })),
+    };
+    // dequeuing from a queue nobody feeds blocks forever
+    let graph = tfpb::graph()
+        .node(placeholder_f32("input"))
+        .node(tfpb::node().name("op").op("Identity").input("input").attr("T", DataType::DtFloat))
+        .node(
+            tfpb::node()
+                .name("queue")
+                .op("FIFOQueueV2")
+                .attr("component_types", float_list.clone()),
+        )
+        .node(
+            tfpb::node()
+                .name("dequeue")
+                .op("QueueDequeueV2")
+                .input("queue")
+                .attr("component_types", float_list),
+        );
+    let graph = graph.write_to_bytes().unwrap();
+    let mut tf = tract_tensorflow::conform::tf::for_slice(&graph).unwrap();
+    tf.set_timeout(Some(std::time::Duration::from_millis(100)));
+    let err = tf.run(vec![], "dequeue").unwrap_err();
+    assert_eq!(err.to_string(), "TensorFlow run timed out after 100ms");
+    let found = tf.run(vec![("input", input.clone())], "op").unwrap();
+    assert_eq!(found, vec![input]);
+}